@@ -0,0 +1,222 @@
+use std::cmp::Ordering;
+
+use crate::deadline::{Cancelled, WorkCounter};
+use crate::insertionsort::binary_insertion_sort_from;
+use crate::quicksort::SortConfig;
+
+// Consecutive wins from one side before switching to galloping.
+const MIN_GALLOP: usize = 7;
+
+pub fn adaptive_merge_sort<T, F>(base: &mut [T], compar: &F)
+where
+    T: Clone,
+    F: Fn(&T, &T) -> Ordering,
+{
+    // Without a deadline the sort always completes.
+    let _ = adaptive_merge_sort_with_config(base, compar, &SortConfig::default());
+}
+
+// Only the deadline of the config applies. It is checked between runs and
+// between the final merges, not inside a merge.
+pub fn adaptive_merge_sort_with_config<T, F>(
+    base: &mut [T],
+    compar: &F,
+    config: &SortConfig,
+) -> Result<(), Cancelled>
+where
+    T: Clone,
+    F: Fn(&T, &T) -> Ordering,
+{
+    let len = base.len();
+    if len <= 1 {
+        return Ok(());
+    }
+
+    let min_run = min_run_length(len);
+    // Pending runs as (start, length); adjacent and in order.
+    let mut runs: Vec<(usize, usize)> = Vec::with_capacity(64);
+    let mut start = 0;
+    let mut work = WorkCounter::default();
+
+    while start < len {
+        let mut run_len = count_run_and_make_ascending(&mut base[start..], compar);
+        if run_len < min_run {
+            let forced = min_run.min(len - start);
+            binary_insertion_sort_from(&mut base[start..start + forced], run_len, compar);
+            run_len = forced;
+        }
+
+        runs.push((start, run_len));
+        merge_collapse(base, &mut runs, compar);
+        start += run_len;
+        if work.add(run_len) {
+            config.check_deadline()?;
+        }
+    }
+
+    while runs.len() > 1 {
+        config.check_deadline()?;
+        let mut n = runs.len() - 2;
+        if n > 0 && runs[n - 1].1 < runs[n + 1].1 {
+            n -= 1;
+        }
+        merge_at(base, &mut runs, n, compar);
+    }
+    Ok(())
+}
+
+fn min_run_length(mut n: usize) -> usize {
+    let mut r = 0;
+    while n >= 64 {
+        r |= n & 1;
+        n >>= 1;
+    }
+    n + r
+}
+
+// Returns the length of the run starting at arr[0]. Strictly descending runs
+// are reversed in place; non-strict ones would break stability.
+fn count_run_and_make_ascending<T, F>(arr: &mut [T], compar: &F) -> usize
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    if arr.len() <= 1 {
+        return arr.len();
+    }
+
+    let mut end = 2;
+    if compar(&arr[1], &arr[0]) == Ordering::Less {
+        while end < arr.len() && compar(&arr[end], &arr[end - 1]) == Ordering::Less {
+            end += 1;
+        }
+        arr[..end].reverse();
+    } else {
+        while end < arr.len() && compar(&arr[end], &arr[end - 1]) != Ordering::Less {
+            end += 1;
+        }
+    }
+    end
+}
+
+// Keeps run lengths growing like Fibonacci numbers from the top of the stack
+// down, which bounds the stack depth and keeps merges balanced.
+fn merge_collapse<T, F>(base: &mut [T], runs: &mut Vec<(usize, usize)>, compar: &F)
+where
+    T: Clone,
+    F: Fn(&T, &T) -> Ordering,
+{
+    while runs.len() > 1 {
+        let mut n = runs.len() - 2;
+        if (n > 0 && runs[n - 1].1 <= runs[n].1 + runs[n + 1].1)
+            || (n > 1 && runs[n - 2].1 <= runs[n - 1].1 + runs[n].1)
+        {
+            if runs[n - 1].1 < runs[n + 1].1 {
+                n -= 1;
+            }
+        } else if runs[n].1 > runs[n + 1].1 {
+            break;
+        }
+        merge_at(base, runs, n, compar);
+    }
+}
+
+fn merge_at<T, F>(base: &mut [T], runs: &mut Vec<(usize, usize)>, n: usize, compar: &F)
+where
+    T: Clone,
+    F: Fn(&T, &T) -> Ordering,
+{
+    let (start, len1) = runs[n];
+    let (_, len2) = runs[n + 1];
+    runs[n] = (start, len1 + len2);
+    runs.remove(n + 1);
+
+    let run = &mut base[start..start + len1 + len2];
+
+    // Elements of the left run not greater than the first right element, and
+    // elements of the right run not less than the last left element, are
+    // already in their final place.
+    let skip = gallop(&run[..len1], |x| compar(x, &run[len1]) != Ordering::Greater);
+    if skip == len1 {
+        return;
+    }
+    let keep = gallop(&run[len1..], |x| {
+        compar(x, &run[len1 - 1]) == Ordering::Less
+    });
+
+    merge_lo(&mut run[skip..len1 + keep], len1 - skip, compar);
+}
+
+fn merge_lo<T, F>(base: &mut [T], mid: usize, compar: &F)
+where
+    T: Clone,
+    F: Fn(&T, &T) -> Ordering,
+{
+    let left = base[..mid].to_vec();
+    let mut i = 0;
+    let mut j = mid;
+    let mut k = 0;
+    let mut left_wins = 0;
+    let mut right_wins = 0;
+
+    while i < left.len() && j < base.len() {
+        if left_wins >= MIN_GALLOP || right_wins >= MIN_GALLOP {
+            let n = gallop(&left[i..], |x| compar(x, &base[j]) != Ordering::Greater);
+            for x in &left[i..i + n] {
+                base[k] = x.clone();
+                k += 1;
+            }
+            i += n;
+            if i == left.len() {
+                break;
+            }
+
+            let m = gallop(&base[j..], |x| compar(x, &left[i]) == Ordering::Less);
+            for t in j..j + m {
+                base[k] = base[t].clone();
+                k += 1;
+            }
+            j += m;
+
+            if n < MIN_GALLOP && m < MIN_GALLOP {
+                left_wins = 0;
+                right_wins = 0;
+            }
+            continue;
+        }
+
+        if compar(&base[j], &left[i]) == Ordering::Less {
+            base[k] = base[j].clone();
+            j += 1;
+            right_wins += 1;
+            left_wins = 0;
+        } else {
+            base[k] = left[i].clone();
+            i += 1;
+            left_wins += 1;
+            right_wins = 0;
+        }
+        k += 1;
+    }
+
+    // Whatever is left of the right run is already in place.
+    while i < left.len() {
+        base[k] = left[i].clone();
+        i += 1;
+        k += 1;
+    }
+}
+
+// Number of leading elements satisfying `pred`, which must hold on a prefix
+// of `arr`. Exponential search first, so short answers are found quickly.
+fn gallop<T, P>(arr: &[T], pred: P) -> usize
+where
+    P: Fn(&T) -> bool,
+{
+    let mut hi = 1;
+    while hi <= arr.len() && pred(&arr[hi - 1]) {
+        hi *= 2;
+    }
+    let lo = hi / 2;
+    let hi = hi.min(arr.len());
+    lo + arr[lo..hi].partition_point(pred)
+}
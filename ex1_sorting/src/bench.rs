@@ -0,0 +1,112 @@
+use std::cmp::Ordering;
+use std::time::{Duration, Instant};
+
+use crate::compar::compare;
+use crate::deadline::{Cancelled, Deadline};
+use crate::generate::{generate_i64, DISTRIBUTIONS};
+use crate::memory::{format_bytes, MemoryUsage, PhaseMemory};
+use crate::quicksort::SortConfig;
+use crate::sorter::{sorters, Sorter};
+
+type Compar = fn(&i64, &i64) -> Ordering;
+
+// Each sort gets `time_limit`; a sort that runs out is reported as timed out
+// and the benchmark moves on to the next one.
+pub fn run_benchmark(
+    size: usize,
+    disorder: f64,
+    seed: u64,
+    config: &SortConfig,
+    time_limit: Duration,
+) {
+    println!(
+        "Benchmark on {} i64 values (nearly sorted: {:.2}% displaced, seed {})",
+        size,
+        disorder * 100.0,
+        seed
+    );
+    println!(
+        "Quick Sort: insertion cutoff {}, {:?} pivot",
+        config.insertion_cutoff, config.pivot
+    );
+    println!("Time limit per sort: {:?}", time_limit);
+    let sorters = sorters::<i64, Compar>();
+    print!("{:<16}", "Distribution");
+    for sorter in sorters {
+        print!("{:>24}", sorter.name());
+    }
+    println!();
+    print!("{:<16}", "Stable");
+    for sorter in sorters {
+        print!("{:>24}", if sorter.is_stable() { "yes" } else { "no" });
+    }
+    println!();
+
+    let mut memory_rows = Vec::new();
+    for distribution in DISTRIBUTIONS {
+        let data = generate_i64(distribution, size, disorder, seed);
+        let mut memory_row = Vec::new();
+        print!("{:<16}", distribution);
+        for sorter in sorters {
+            let mut copy = data.clone();
+            let (result, memory) = time_sort(&mut copy, sorter, config, time_limit);
+            let cell = match result {
+                Ok(elapsed) => {
+                    let sorted = copy.windows(2).all(|w| w[0] <= w[1]);
+                    format!("{:.4?}{}", elapsed, if sorted { "" } else { " (!)" })
+                }
+                Err(cancelled) => cancelled.to_string(),
+            };
+            print!("{:>24}", cell);
+            memory_row.push(memory);
+        }
+        println!();
+        memory_rows.push((distribution, memory_row));
+    }
+
+    print_memory_table("Peak RSS", &memory_rows, |m| m.peak_rss);
+    print_memory_table("Allocated", &memory_rows, |m| m.allocated);
+}
+
+fn print_memory_table(
+    title: &str,
+    rows: &[(&str, Vec<MemoryUsage>)],
+    value: fn(&MemoryUsage) -> Option<usize>,
+) {
+    if rows
+        .iter()
+        .flat_map(|(_, row)| row)
+        .all(|m| value(m).is_none())
+    {
+        return;
+    }
+
+    println!("\n{}", title);
+    for (distribution, row) in rows {
+        print!("{:<16}", distribution);
+        for memory in row {
+            let cell = value(memory).map_or("-".to_string(), format_bytes);
+            print!("{:>24}", cell);
+        }
+        println!();
+    }
+}
+
+fn time_sort(
+    data: &mut [i64],
+    sorter: &dyn Sorter<i64, Compar>,
+    config: &SortConfig,
+    time_limit: Duration,
+) -> (Result<Duration, Cancelled>, MemoryUsage) {
+    let compar: Compar = compare;
+    let config = SortConfig {
+        deadline: Some(Deadline::after(time_limit)),
+        ..config.clone()
+    };
+    let memory = PhaseMemory::start();
+    let start = Instant::now();
+    let result = sorter
+        .sort_with_config(data, &compar, &config)
+        .map(|_| start.elapsed());
+    (result, memory.finish())
+}
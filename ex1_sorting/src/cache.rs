@@ -0,0 +1,192 @@
+// Binary cache of parsed records, so sorting the same CSV again skips
+// parse_csv. The file is a header followed by one array of little-endian
+// 8-byte values per column:
+//
+//   magic, version, delimiter, input size, input mtime (s, ns), count
+//   id[count], value1[count], value2[count] (f64 bits),
+//   line start[count], line end[count], name start[count], name end[count]
+//
+// Names are not stored; they are sliced from the mmapped input, which the
+// raw writer needs anyway. A cache whose size or mtime does not match the
+// input is ignored and rewritten. The input is not hashed: that would cost a
+// full pass over it on every load, as much as a good part of parsing it.
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::time::UNIX_EPOCH;
+
+use memmap2::Mmap;
+use rayon::prelude::*;
+
+use crate::error::SortError;
+use crate::records::{write_atomically, Record};
+
+const MAGIC: &[u8; 8] = b"SORTCACH";
+const VERSION: u64 = 2;
+const HEADER_WORDS: usize = 7;
+const COLUMNS: usize = 7;
+
+struct Fingerprint {
+    size: u64,
+    mtime_secs: u64,
+    mtime_nanos: u64,
+}
+
+impl Fingerprint {
+    fn of(path: &str) -> Result<Fingerprint, SortError> {
+        let metadata = fs::metadata(path).map_err(SortError::io(path))?;
+        let mtime = metadata
+            .modified()
+            .map_err(SortError::io(path))?
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        Ok(Fingerprint {
+            size: metadata.len(),
+            mtime_secs: mtime.as_secs(),
+            mtime_nanos: mtime.subsec_nanos() as u64,
+        })
+    }
+}
+
+// Returns the cached records and delimiter, or None if there is no cache for
+// this input. A `delimiter` given explicitly must match the cached one.
+pub fn load_cache(
+    cache_path: &str,
+    input_path: &str,
+    input: &[u8],
+    delimiter: Option<u8>,
+) -> Result<Option<(u8, Vec<Record>)>, SortError> {
+    let file = match File::open(cache_path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(SortError::io(cache_path)(e)),
+    };
+    let mmap = unsafe { Mmap::map(&file).map_err(SortError::io(cache_path))? };
+
+    if mmap.len() < HEADER_WORDS * 8 || &mmap[..8] != MAGIC {
+        return Ok(None);
+    }
+    let words: [u64; HEADER_WORDS] =
+        std::array::from_fn(|i| u64::from_le_bytes(mmap[i * 8..i * 8 + 8].try_into().unwrap()));
+    let [_, version, cached_delimiter, size, mtime_secs, mtime_nanos, count] = words;
+    let count = count as usize;
+    let body_words = mmap.len() / 8 - HEADER_WORDS;
+    if version != VERSION
+        || delimiter.is_some_and(|delimiter| delimiter as u64 != cached_delimiter)
+        || mmap.len() % 8 != 0
+        || !body_words.is_multiple_of(COLUMNS)
+        || body_words / COLUMNS != count
+    {
+        return Ok(None);
+    }
+
+    let fingerprint = Fingerprint::of(input_path)?;
+    if (size, mtime_secs, mtime_nanos)
+        != (
+            fingerprint.size,
+            fingerprint.mtime_secs,
+            fingerprint.mtime_nanos,
+        )
+    {
+        return Ok(None);
+    }
+
+    let column = |index: usize| {
+        let start = (HEADER_WORDS + index * count) * 8;
+        mmap[start..start + count * 8]
+            .chunks_exact(8)
+            .map(|word| u64::from_le_bytes(word.try_into().unwrap()))
+    };
+    let offsets = column(3).zip(column(4)).zip(column(5).zip(column(6))).map(
+        |((line_start, line_end), (name_start, name_end))| {
+            (
+                line_start as usize,
+                line_end as usize,
+                name_start as usize,
+                name_end as usize,
+            )
+        },
+    );
+    if offsets
+        .clone()
+        .any(|(line_start, line_end, name_start, name_end)| {
+            line_start > line_end
+                || name_start > name_end
+                || line_end > input.len()
+                || name_end > input.len()
+        })
+    {
+        return Ok(None);
+    }
+
+    let records = column(0)
+        .zip(column(1))
+        .zip(column(2))
+        .zip(offsets)
+        .map(
+            |(((id, value1), value2), (line_start, line_end, name_start, name_end))| {
+                let name_bytes = &input[name_start..name_end];
+                Record {
+                    id: id as i64,
+                    name: String::from_utf8_lossy(name_bytes).as_ref().into(),
+                    value1: value1 as i64,
+                    value2: f64::from_bits(value2),
+                    line_range: (line_start, line_end),
+                }
+            },
+        )
+        .collect();
+
+    Ok(Some((cached_delimiter as u8, records)))
+}
+
+pub fn write_cache(
+    cache_path: &str,
+    input_path: &str,
+    input: &[u8],
+    delimiter: u8,
+    records: &[Record],
+) -> Result<(), SortError> {
+    let fingerprint = Fingerprint::of(input_path)?;
+    let name_ranges: Vec<(usize, usize)> = records
+        .par_iter()
+        .map(|record| {
+            let (line_start, line_end) = record.line_range;
+            let line = &input[line_start..line_end];
+            // parse_record accepted the line, so both delimiters are there.
+            let c1 = memchr::memchr(delimiter, line).unwrap();
+            let c2 = c1 + 1 + memchr::memchr(delimiter, &line[c1 + 1..]).unwrap();
+            (line_start + c1 + 1, line_start + c2)
+        })
+        .collect();
+
+    write_atomically(cache_path, |writer| {
+        writer.write_all(MAGIC)?;
+        for word in [
+            VERSION,
+            delimiter as u64,
+            fingerprint.size,
+            fingerprint.mtime_secs,
+            fingerprint.mtime_nanos,
+            records.len() as u64,
+        ] {
+            writer.write_all(&word.to_le_bytes())?;
+        }
+
+        let columns: [&dyn Fn(usize) -> u64; COLUMNS] = [
+            &|i| records[i].id as u64,
+            &|i| records[i].value1 as u64,
+            &|i| records[i].value2.to_bits(),
+            &|i| records[i].line_range.0 as u64,
+            &|i| records[i].line_range.1 as u64,
+            &|i| name_ranges[i].0 as u64,
+            &|i| name_ranges[i].1 as u64,
+        ];
+        for column in columns {
+            for i in 0..records.len() {
+                writer.write_all(&column(i).to_le_bytes())?;
+            }
+        }
+        Ok(())
+    })
+}
@@ -0,0 +1,30 @@
+use crate::deadline::Cancelled;
+
+// Decorate-sort-undecorate: each key is computed once, the (key, index) pairs
+// are sorted by `sort`, and the resulting permutation is applied to `base`.
+// If the sort is cancelled, `base` is left untouched.
+pub fn sort_by_cached_key<T, K, KF, S>(base: &mut [T], key: KF, sort: S) -> Result<(), Cancelled>
+where
+    KF: Fn(&T) -> K,
+    S: FnOnce(&mut [(K, usize)]) -> Result<(), Cancelled>,
+{
+    if base.len() <= 1 {
+        return Ok(());
+    }
+
+    let mut keyed: Vec<(K, usize)> = base.iter().map(&key).zip(0..).collect();
+    sort(&mut keyed)?;
+
+    // keyed[i].1 is the original position of the element that belongs at i.
+    // Earlier swaps may have moved it, so follow the chain of positions
+    // already filled until reaching where it is now.
+    for i in 0..base.len() {
+        let mut index = keyed[i].1;
+        while index < i {
+            index = keyed[index].1;
+        }
+        keyed[i].1 = index;
+        base.swap(i, index);
+    }
+    Ok(())
+}
@@ -0,0 +1,158 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::fs::File;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::{BufRead, BufReader};
+
+use crate::error::SortError;
+use crate::records::{detect_delimiter, parse_record, Record};
+
+type RecordCompar<'a> = dyn Fn(&Record, &Record) -> Ordering + 'a;
+
+pub struct CsvDigest {
+    pub lines: usize,
+    pub hash: u64,
+    pub first_violation: Option<(usize, String)>,
+}
+
+pub enum Verification {
+    Ok { lines: usize },
+    OutOfOrder { line: usize, content: String },
+    LineCountMismatch { expected: usize, found: usize },
+    HashMismatch,
+}
+
+impl Verification {
+    pub fn is_ok(&self) -> bool {
+        matches!(self, Verification::Ok { .. })
+    }
+}
+
+impl fmt::Display for Verification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Verification::Ok { lines } => write!(f, "OK ({} records)", lines),
+            Verification::OutOfOrder { line, content } => {
+                write!(f, "FAILED: line {} is out of order: {}", line, content)
+            }
+            Verification::LineCountMismatch { expected, found } => write!(
+                f,
+                "FAILED: {} records in output, {} in input",
+                found, expected
+            ),
+            Verification::HashMismatch => {
+                write!(f, "FAILED: output is not a permutation of the input")
+            }
+        }
+    }
+}
+
+// Streams the records of a CSV, counting them and summing per-line hashes so
+// the digest does not depend on the order of the lines. When `cmp` is given,
+// the first record that is smaller than its predecessor is reported.
+pub fn scan_csv<R: BufRead>(
+    mut reader: R,
+    path: &str,
+    cmp: Option<&RecordCompar<'_>>,
+    delimiter: u8,
+) -> Result<CsvDigest, SortError> {
+    let mut digest = CsvDigest {
+        lines: 0,
+        hash: 0,
+        first_violation: None,
+    };
+    let mut buffer = Vec::with_capacity(256);
+    let mut previous: Option<Record> = None;
+    let mut line_number = 0;
+
+    loop {
+        buffer.clear();
+        let read = reader
+            .read_until(b'\n', &mut buffer)
+            .map_err(SortError::io(path))?;
+        if read == 0 {
+            break;
+        }
+        line_number += 1;
+
+        let line = buffer.strip_suffix(b"\n").unwrap_or(&buffer);
+        if line.is_empty() {
+            continue;
+        }
+        let record = parse_record(line, 0, delimiter).map_err(|message| SortError::Parse {
+            path: path.to_string(),
+            line: line_number,
+            message,
+        })?;
+
+        digest.lines += 1;
+        let mut hasher = DefaultHasher::new();
+        line.hash(&mut hasher);
+        digest.hash = digest.hash.wrapping_add(hasher.finish());
+
+        if let Some(cmp) = cmp {
+            if digest.first_violation.is_none() {
+                if let Some(prev) = &previous {
+                    if cmp(prev, &record) == Ordering::Greater {
+                        digest.first_violation =
+                            Some((line_number, String::from_utf8_lossy(line).into_owned()));
+                    }
+                }
+                previous = Some(record);
+            }
+        }
+    }
+
+    Ok(digest)
+}
+
+// Readers are paired with their path, used in error messages.
+pub fn verify<R: BufRead, S: BufRead>(
+    output: (R, &str),
+    input: Option<(S, &str)>,
+    cmp: &RecordCompar<'_>,
+    delimiter: u8,
+) -> Result<Verification, SortError> {
+    let sorted = scan_csv(output.0, output.1, Some(cmp), delimiter)?;
+    if let Some((line, content)) = sorted.first_violation {
+        return Ok(Verification::OutOfOrder { line, content });
+    }
+
+    if let Some((input, input_path)) = input {
+        let original = scan_csv(input, input_path, None, delimiter)?;
+        if original.lines != sorted.lines {
+            return Ok(Verification::LineCountMismatch {
+                expected: original.lines,
+                found: sorted.lines,
+            });
+        }
+        if original.hash != sorted.hash {
+            return Ok(Verification::HashMismatch);
+        }
+    }
+
+    Ok(Verification::Ok {
+        lines: sorted.lines,
+    })
+}
+
+pub fn verify_files(
+    output_path: &str,
+    input_path: Option<&str>,
+    cmp: fn(&Record, &Record) -> Ordering,
+    delimiter: Option<u8>,
+) -> Result<Verification, SortError> {
+    let mut output = BufReader::new(File::open(output_path).map_err(SortError::io(output_path))?);
+    let delimiter = match delimiter {
+        Some(delimiter) => delimiter,
+        None => detect_delimiter(output.fill_buf().map_err(SortError::io(output_path))?),
+    };
+    let input = match input_path {
+        Some(path) => Some((
+            BufReader::new(File::open(path).map_err(SortError::io(path))?),
+            path,
+        )),
+        None => None,
+    };
+    verify((output, output_path), input, &cmp, delimiter)
+}
@@ -0,0 +1,466 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::bench::run_benchmark;
+use crate::check::verify_files;
+use crate::compar::Collation;
+use crate::error::SortError;
+use crate::expr::Expr;
+use crate::generate::DISTRIBUTIONS;
+use crate::group::Keep;
+use crate::merge::run_merge;
+use crate::output::OutputSpec;
+use crate::quicksort::{PivotStrategy, SortConfig};
+use crate::records::{column_comparator, parse_delimiter, run_sort, Record, SortOptions};
+use crate::report::{run_report, ReportOptions};
+use crate::sorter::{find_sorter, sorter_names};
+use crate::test::{run_sorting_test, DATA_TYPES};
+
+const USAGE: &str = "Usage:
+  sorting                                   interactive menu
+  sorting sort <input|-> <output|-> [--algorithm <name>] [--column <Name|Value1|Value2>] [--verify]
+               [--format <raw|csv|jsonl>] [--columns <Id,Name,Value1,Value2>] [--precision <digits>]
+               [--cutoff <n>] [--pivot <first|middle|random|median3|ninther>] [--seed <n>]
+               [--parse-chunks <n>] [--delimiter <char|tab|auto>] [--output-delimiter <char|tab>]
+               [--unique <first|last>] [--group-count] [--collation <binary|nocase|natural>]
+               [--time-limit <seconds>] [--cache <file>] [--no-progress] [--key <expression>]
+               [--filter <condition>] [--lenient]
+  sorting merge <sorted.csv>... <output|-> --column <Name|Value1|Value2>
+                [--delimiter <char|tab|auto>]
+  sorting bench [--size <n>] [--disorder <fraction>] [--seed <n>] [--cutoff <n>] [--pivot <name>]
+                [--time-limit <seconds>]
+  sorting report <report.md> [--input <records.csv>] [--distributions <list|all|none>]
+                 [--algorithms <list|all>] [--size <n>] [--disorder <fraction>] [--seed <n>]
+                 [--cutoff <n>] [--pivot <name>] [--time-limit <seconds>]
+  sorting test <values.txt> [--type <i32|i64|f32|f64|String>] [--algorithm <name>]
+  sorting algorithms                        list the available algorithms
+  sorting check <sorted.csv> --column <Name|Value1|Value2> [--input <original.csv>]
+                [--delimiter <char|tab|auto>]";
+
+struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+    flags: Vec<String>,
+}
+
+impl Args {
+    fn parse(
+        args: &[String],
+        value_options: &[&str],
+        flag_options: &[&str],
+    ) -> Result<Args, SortError> {
+        let mut positional = Vec::new();
+        let mut options = HashMap::new();
+        let mut flags = Vec::new();
+        let mut iter = args.iter();
+
+        while let Some(arg) = iter.next() {
+            if !arg.starts_with("--") {
+                positional.push(arg.clone());
+                continue;
+            }
+            if flag_options.contains(&arg.as_str()) {
+                flags.push(arg.clone());
+                continue;
+            }
+
+            let (name, inline_value) = match arg.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (arg.as_str(), None),
+            };
+            if !value_options.contains(&name) {
+                return Err(SortError::Usage(format!(
+                    "unknown option '{}'\n{}",
+                    name, USAGE
+                )));
+            }
+            let value = match inline_value {
+                Some(value) => value,
+                None => iter.next().cloned().ok_or_else(|| {
+                    SortError::Usage(format!("option '{}' requires a value", name))
+                })?,
+            };
+            options.insert(name.to_string(), value);
+        }
+
+        Ok(Args {
+            positional,
+            options,
+            flags,
+        })
+    }
+
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(String::as_str)
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|flag| flag == name)
+    }
+}
+
+pub fn run(args: &[String]) -> Result<(), SortError> {
+    match args.first().map(String::as_str) {
+        Some("sort") => run_sort_command(&args[1..]),
+        Some("check") => run_check(&args[1..]),
+        Some("merge") => run_merge_command(&args[1..]),
+        Some("bench") => run_bench(&args[1..]),
+        Some("test") => run_test_command(&args[1..]),
+        Some("report") => run_report_command(&args[1..]),
+        Some("algorithms") => {
+            for name in sorter_names() {
+                println!("{}", name);
+            }
+            Ok(())
+        }
+        Some("help") | Some("--help") | Some("-h") => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        Some(other) => Err(SortError::Usage(format!(
+            "unknown command '{}'\n{}",
+            other, USAGE
+        ))),
+        None => Err(SortError::Usage(USAGE.to_string())),
+    }
+}
+
+fn run_sort_command(args: &[String]) -> Result<(), SortError> {
+    let args = Args::parse(
+        args,
+        &[
+            "--algorithm",
+            "--column",
+            "--format",
+            "--columns",
+            "--precision",
+            "--cutoff",
+            "--pivot",
+            "--seed",
+            "--parse-chunks",
+            "--delimiter",
+            "--output-delimiter",
+            "--unique",
+            "--collation",
+            "--time-limit",
+            "--cache",
+            "--key",
+            "--filter",
+        ],
+        &["--verify", "--group-count", "--no-progress", "--lenient"],
+    )?;
+    let [input, output] = args.positional.as_slice() else {
+        return Err(SortError::Usage(format!(
+            "sort expects an input and an output\n{}",
+            USAGE
+        )));
+    };
+
+    let collation = match args.option("--collation") {
+        None => Collation::Binary,
+        Some(name) => Collation::parse(name)
+            .ok_or_else(|| SortError::Usage(format!("invalid value '{}' for --collation", name)))?,
+    };
+    let column = parse_column(args.option("--column").unwrap_or("Name"))?;
+    let key = args
+        .option("--key")
+        .map(|source| Expr::parse_key(source).map_err(invalid_expression(source)))
+        .transpose()?;
+    let filter = args
+        .option("--filter")
+        .map(|source| Expr::parse_filter(source).map_err(invalid_expression(source)))
+        .transpose()?;
+    if key.is_some() && (args.option("--column").is_some() || args.option("--collation").is_some())
+    {
+        return Err(SortError::Usage(
+            "--key cannot be combined with --column or --collation".to_string(),
+        ));
+    }
+    // Deduplicating first would leave every group with a single record.
+    if args.option("--unique").is_some() && args.flag("--group-count") {
+        return Err(SortError::Usage(
+            "--unique cannot be combined with --group-count".to_string(),
+        ));
+    }
+    if collation != Collation::Binary && column != "Name" {
+        return Err(SortError::Usage(
+            "--collation only applies to the Name column".to_string(),
+        ));
+    }
+
+    run_sort(&SortOptions {
+        input,
+        output,
+        algorithm: parse_algorithm(args.option("--algorithm").unwrap_or("merge"))?,
+        column,
+        verify: args.flag("--verify"),
+        output_spec: parse_output_spec(&args)?,
+        config: parse_sort_config(&args)?,
+        delimiter: parse_delimiter_option(&args, "--delimiter")?,
+        parse_chunks: args
+            .option("--parse-chunks")
+            .map(|chunks| parse_number(chunks, "--parse-chunks"))
+            .transpose()?,
+        unique: args
+            .option("--unique")
+            .map(|keep| {
+                Keep::parse(keep).ok_or_else(|| {
+                    SortError::Usage(format!("invalid value '{}' for --unique", keep))
+                })
+            })
+            .transpose()?,
+        group_count: args.flag("--group-count"),
+        collation,
+        time_limit: args
+            .option("--time-limit")
+            .map(parse_time_limit)
+            .transpose()?,
+        cache: args.option("--cache"),
+        progress: !args.flag("--no-progress"),
+        key,
+        filter,
+        lenient: args.flag("--lenient"),
+    })
+}
+
+fn parse_output_spec(args: &Args) -> Result<OutputSpec, SortError> {
+    let mut spec = OutputSpec::default();
+    if let Some(format) = args.option("--format") {
+        spec.format = OutputSpec::parse_format(format)?;
+    }
+    if let Some(columns) = args.option("--columns") {
+        spec.columns = OutputSpec::parse_columns(columns)?;
+    }
+    if let Some(precision) = args.option("--precision") {
+        spec.precision = Some(parse_number(precision, "--precision")?);
+    }
+    spec.delimiter = parse_delimiter_option(args, "--output-delimiter")?;
+    Ok(spec)
+}
+
+fn parse_sort_config(args: &Args) -> Result<SortConfig, SortError> {
+    let mut config = SortConfig::default();
+    if let Some(cutoff) = args.option("--cutoff") {
+        config.insertion_cutoff = parse_number(cutoff, "--cutoff")?;
+    }
+    if let Some(pivot) = args.option("--pivot") {
+        config.pivot = PivotStrategy::parse(pivot)
+            .ok_or_else(|| SortError::Usage(format!("unknown pivot strategy '{}'", pivot)))?;
+    }
+    if let Some(seed) = args.option("--seed") {
+        config.seed = parse_number(seed, "--seed")?;
+    }
+    Ok(config)
+}
+
+fn parse_time_limit(seconds: &str) -> Result<Duration, SortError> {
+    parse_number::<f64>(seconds, "--time-limit")
+        .ok()
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+        .ok_or_else(|| SortError::Usage(format!("invalid value '{}' for --time-limit", seconds)))
+}
+
+fn run_check(args: &[String]) -> Result<(), SortError> {
+    let args = Args::parse(args, &["--column", "--input", "--delimiter"], &[])?;
+    let [output_path] = args.positional.as_slice() else {
+        return Err(SortError::Usage(format!(
+            "check expects exactly one file\n{}",
+            USAGE
+        )));
+    };
+
+    // The order of the file is unknown, so guessing a column would report
+    // files sorted by another one as unsorted.
+    let column = args
+        .option("--column")
+        .ok_or_else(|| SortError::Usage(format!("check requires --column\n{}", USAGE)))?;
+    let column = parse_column(column)?;
+    let cmp =
+        column_comparator(column).ok_or_else(|| SortError::InvalidColumn(column.to_string()))?;
+
+    let verification = verify_files(
+        output_path,
+        args.option("--input"),
+        cmp,
+        parse_delimiter_option(&args, "--delimiter")?,
+    )?;
+    println!("{}: {}", output_path, verification);
+    if verification.is_ok() {
+        Ok(())
+    } else {
+        Err(SortError::VerificationFailed(output_path.to_string()))
+    }
+}
+
+fn run_merge_command(args: &[String]) -> Result<(), SortError> {
+    let args = Args::parse(args, &["--column", "--delimiter"], &[])?;
+    let Some((output, inputs)) = args
+        .positional
+        .split_last()
+        .filter(|(_, inputs)| !inputs.is_empty())
+    else {
+        return Err(SortError::Usage(format!(
+            "merge expects at least one input and an output\n{}",
+            USAGE
+        )));
+    };
+
+    // As for check: the inputs' order is unknown, and a wrong guess would fail
+    // partway through, after part of the output is written.
+    let column = args
+        .option("--column")
+        .ok_or_else(|| SortError::Usage(format!("merge requires --column\n{}", USAGE)))?;
+
+    run_merge(
+        inputs,
+        output,
+        parse_column(column)?,
+        parse_delimiter_option(&args, "--delimiter")?,
+    )
+}
+
+fn run_test_command(args: &[String]) -> Result<(), SortError> {
+    let args = Args::parse(args, &["--type", "--algorithm"], &[])?;
+    let [path] = args.positional.as_slice() else {
+        return Err(SortError::Usage(format!(
+            "test expects exactly one file\n{}",
+            USAGE
+        )));
+    };
+
+    let type_name = args.option("--type").unwrap_or("i64");
+    let data_type = DATA_TYPES
+        .into_iter()
+        .find(|data_type| data_type.eq_ignore_ascii_case(type_name))
+        .ok_or_else(|| SortError::Usage(format!("invalid value '{}' for --type", type_name)))?;
+    run_sorting_test(
+        parse_algorithm(args.option("--algorithm").unwrap_or("merge"))?,
+        data_type,
+        path,
+    )
+}
+
+fn run_report_command(args: &[String]) -> Result<(), SortError> {
+    let args = Args::parse(
+        args,
+        &[
+            "--input",
+            "--distributions",
+            "--algorithms",
+            "--size",
+            "--disorder",
+            "--seed",
+            "--cutoff",
+            "--pivot",
+            "--time-limit",
+        ],
+        &[],
+    )?;
+    let [output] = args.positional.as_slice() else {
+        return Err(SortError::Usage(format!(
+            "report expects exactly one output file\n{}",
+            USAGE
+        )));
+    };
+
+    let input = args.option("--input");
+    // Without an input file the report is over all generated distributions.
+    let distributions = match args.option("--distributions") {
+        None if input.is_some() => Vec::new(),
+        None | Some("all") => DISTRIBUTIONS.to_vec(),
+        Some("none") => Vec::new(),
+        Some(list) => list
+            .split(',')
+            .map(|name| {
+                DISTRIBUTIONS
+                    .into_iter()
+                    .find(|distribution| {
+                        distribution
+                            .replace(' ', "-")
+                            .eq_ignore_ascii_case(name.trim())
+                    })
+                    .ok_or_else(|| SortError::Usage(format!("unknown distribution '{}'", name)))
+            })
+            .collect::<Result<_, _>>()?,
+    };
+    let algorithms = match args.option("--algorithms").unwrap_or("merge,quick") {
+        "all" => sorter_names(),
+        list => list
+            .split(',')
+            .map(|name| parse_algorithm(name.trim()))
+            .collect::<Result<_, _>>()?,
+    };
+
+    run_report(&ReportOptions {
+        output,
+        input,
+        distributions,
+        size: parse_number(args.option("--size").unwrap_or("100000"), "--size")?,
+        disorder: parse_number(args.option("--disorder").unwrap_or("0.01"), "--disorder")?,
+        seed: parse_number(args.option("--seed").unwrap_or("42"), "--seed")?,
+        algorithms,
+        config: parse_sort_config(&args)?,
+        time_limit: parse_time_limit(args.option("--time-limit").unwrap_or("600"))?,
+    })
+}
+
+fn run_bench(args: &[String]) -> Result<(), SortError> {
+    let args = Args::parse(
+        args,
+        &[
+            "--size",
+            "--disorder",
+            "--seed",
+            "--cutoff",
+            "--pivot",
+            "--time-limit",
+        ],
+        &[],
+    )?;
+    let size = parse_number(args.option("--size").unwrap_or("1000000"), "--size")?;
+    let disorder = parse_number(args.option("--disorder").unwrap_or("0.01"), "--disorder")?;
+    let seed = parse_number(args.option("--seed").unwrap_or("42"), "--seed")?;
+
+    // Sorts running longer than 10 minutes count as failed.
+    let time_limit = parse_time_limit(args.option("--time-limit").unwrap_or("600"))?;
+
+    run_benchmark(size, disorder, seed, &parse_sort_config(&args)?, time_limit);
+    Ok(())
+}
+
+fn parse_number<N: std::str::FromStr>(value: &str, option: &str) -> Result<N, SortError> {
+    value
+        .parse()
+        .map_err(|_| SortError::Usage(format!("invalid value '{}' for {}", value, option)))
+}
+
+// "auto" (or no option) leaves the delimiter to detection.
+fn parse_delimiter_option(args: &Args, option: &str) -> Result<Option<u8>, SortError> {
+    match args.option(option) {
+        None | Some("auto") => Ok(None),
+        Some(name) => parse_delimiter(name)
+            .map(Some)
+            .ok_or_else(|| SortError::Usage(format!("invalid value '{}' for {}", name, option))),
+    }
+}
+
+fn invalid_expression(source: &str) -> impl FnOnce(String) -> SortError + '_ {
+    move |message| SortError::InvalidExpression {
+        expression: source.to_string(),
+        message,
+    }
+}
+
+fn parse_column(name: &str) -> Result<&'static str, SortError> {
+    ["Name", "Value1", "Value2"]
+        .into_iter()
+        .find(|column| column.eq_ignore_ascii_case(name))
+        .ok_or_else(|| SortError::InvalidColumn(name.to_string()))
+}
+
+fn parse_algorithm(name: &str) -> Result<&'static str, SortError> {
+    find_sorter::<Record, fn(&Record, &Record) -> Ordering>(name)
+        .map(|sorter| sorter.name())
+        .ok_or_else(|| SortError::InvalidAlgorithm(name.to_string()))
+}
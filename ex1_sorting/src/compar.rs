@@ -35,3 +35,64 @@ pub fn compare_str_lex(a: &str, b: &str) -> Ordering {
     // If all compared bytes are equal, the shorter string is "Less"
     a_bytes.len().cmp(&b_bytes.len())
 }
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Collation {
+    // Byte order, as compare_str_lex.
+    Binary,
+    CaseInsensitive,
+    // Runs of digits compare by numeric value, so "item9" < "item10".
+    Natural,
+}
+
+impl Collation {
+    pub fn parse(name: &str) -> Option<Collation> {
+        match name.to_ascii_lowercase().as_str() {
+            "binary" => Some(Collation::Binary),
+            "nocase" | "case-insensitive" => Some(Collation::CaseInsensitive),
+            "natural" => Some(Collation::Natural),
+            _ => None,
+        }
+    }
+}
+
+pub fn fold_case(s: &str) -> String {
+    s.to_lowercase()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum KeyPart {
+    // Digits without leading zeros, compared by length first.
+    Number(usize, String),
+    Text(String),
+}
+
+pub fn natural_key(s: &str) -> Vec<KeyPart> {
+    let mut parts = Vec::new();
+    let mut rest = s;
+    while let Some(first) = rest.chars().next() {
+        let is_digit = first.is_ascii_digit();
+        let end = rest
+            .find(|c: char| c.is_ascii_digit() != is_digit)
+            .unwrap_or(rest.len());
+        let (run, tail) = rest.split_at(end);
+        parts.push(if is_digit {
+            let digits = run.trim_start_matches('0');
+            KeyPart::Number(digits.len(), digits.to_string())
+        } else {
+            KeyPart::Text(run.to_string())
+        });
+        rest = tail;
+    }
+    parts
+}
+
+// Both comparators rebuild their keys on every call; sorts that can should
+// compute the keys once with `sort_by_cached_key`.
+pub fn compare_str_nocase(a: &str, b: &str) -> Ordering {
+    fold_case(a).cmp(&fold_case(b))
+}
+
+pub fn compare_str_natural(a: &str, b: &str) -> Ordering {
+    natural_key(a).cmp(&natural_key(b))
+}
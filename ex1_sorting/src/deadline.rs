@@ -0,0 +1,60 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+// Sorts only look at the clock for ranges at least this long, which keeps the
+// checks rare while still bounding the work between two of them.
+pub const CHECK_MIN_LEN: usize = 4096;
+
+// For sorts whose steps vary too much in cost to check the clock every so
+// many of them: counts the work done since the last check instead.
+#[derive(Debug, Default)]
+pub struct WorkCounter(usize);
+
+impl WorkCounter {
+    // True once CHECK_MIN_LEN units of work have been added since it last was.
+    pub fn add(&mut self, work: usize) -> bool {
+        self.0 += work;
+        if self.0 >= CHECK_MIN_LEN {
+            self.0 = 0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Deadline {
+    start: Instant,
+    budget: Duration,
+}
+
+impl Deadline {
+    pub fn after(budget: Duration) -> Self {
+        Deadline {
+            start: Instant::now(),
+            budget,
+        }
+    }
+
+    pub fn check(&self) -> Result<(), Cancelled> {
+        if self.start.elapsed() > self.budget {
+            Err(Cancelled {
+                budget: self.budget,
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Cancelled {
+    pub budget: Duration,
+}
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "timed out after {:?}", self.budget)
+    }
+}
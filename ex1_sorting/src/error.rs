@@ -0,0 +1,98 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use inquire::InquireError;
+
+use crate::deadline::Cancelled;
+
+#[derive(Debug)]
+pub enum SortError {
+    Io {
+        path: String,
+        source: io::Error,
+    },
+    Parse {
+        path: String,
+        line: usize,
+        message: String,
+    },
+    Unsorted {
+        path: String,
+        line: usize,
+    },
+    InvalidColumn(String),
+    InvalidAlgorithm(String),
+    InvalidExpression {
+        expression: String,
+        message: String,
+    },
+    OutputIsInput(String),
+    VerificationFailed(String),
+    TimedOut(Cancelled),
+    Usage(String),
+    Prompt(InquireError),
+}
+
+impl SortError {
+    // For use with `map_err`, attaching the path the failing operation was on.
+    pub fn io(path: &str) -> impl FnOnce(io::Error) -> SortError + '_ {
+        move |source| SortError::Io {
+            path: path.to_string(),
+            source,
+        }
+    }
+}
+
+impl fmt::Display for SortError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SortError::Io { path, source } => write!(f, "{}: {}", display_path(path), source),
+            SortError::Parse {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", display_path(path), line, message),
+            SortError::Unsorted { path, line } => {
+                write!(f, "{}:{}: record is out of order", display_path(path), line)
+            }
+            SortError::InvalidColumn(column) => write!(f, "invalid column '{}'", column),
+            SortError::InvalidAlgorithm(name) => write!(f, "invalid algorithm '{}'", name),
+            SortError::InvalidExpression {
+                expression,
+                message,
+            } => write!(f, "invalid expression '{}': {}", expression, message),
+            SortError::OutputIsInput(path) => {
+                write!(f, "output file '{}' is the same as the input file", path)
+            }
+            SortError::VerificationFailed(path) => write!(f, "{}: verification failed", path),
+            SortError::TimedOut(cancelled) => write!(f, "sorting {}", cancelled),
+            SortError::Usage(message) => write!(f, "{}", message),
+            SortError::Prompt(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+fn display_path(path: &str) -> &str {
+    if path == "-" {
+        "<standard stream>"
+    } else {
+        path
+    }
+}
+
+impl Error for SortError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SortError::Io { source, .. } => Some(source),
+            SortError::Prompt(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<InquireError> for SortError {
+    fn from(e: InquireError) -> Self {
+        SortError::Prompt(e)
+    }
+}
@@ -0,0 +1,560 @@
+// Expressions over the columns of a record: sort keys such as
+// `value1 * value2` or `len(name)`, and row filters such as
+// `value2 > 1000 && name starts_with "a"`. Grammar, loosest binding first:
+//
+//   or         = and ("||" and)*
+//   and        = not ("&&" not)*
+//   not        = "!" not | comparison
+//   comparison = sum (("<" | "<=" | ">" | ">=" | "==" | "!=" |
+//                      "starts_with" | "ends_with" | "contains") sum)?
+//   sum        = term (("+" | "-") term)*
+//   term       = unary (("*" | "/" | "%") unary)*
+//   unary      = "-" unary | primary
+//   primary    = number | string | column | function "(" or ")" | "(" or ")"
+//
+// Columns are id, name, value1 and value2; functions are abs, len and lower.
+// Strings are double-quoted. Every expression is type checked when parsed,
+// so `name + 1` is rejected before any row is read.
+
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::compar::compare_str_lex;
+use crate::records::Record;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Type {
+    Number,
+    Text,
+    Bool,
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Number => write!(f, "a number"),
+            Type::Text => write!(f, "text"),
+            Type::Bool => write!(f, "a condition"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum CompareOp {
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+}
+
+impl CompareOp {
+    fn holds(self, ordering: Option<Ordering>) -> bool {
+        match self {
+            CompareOp::Less => ordering == Some(Ordering::Less),
+            CompareOp::LessEqual => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+            CompareOp::Greater => ordering == Some(Ordering::Greater),
+            CompareOp::GreaterEqual => {
+                matches!(ordering, Some(Ordering::Greater | Ordering::Equal))
+            }
+            CompareOp::Equal => ordering == Some(Ordering::Equal),
+            CompareOp::NotEqual => ordering != Some(Ordering::Equal),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum TextOp {
+    StartsWith,
+    EndsWith,
+    Contains,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Number(f64),
+    Text(String),
+    Id,
+    Name,
+    Value1,
+    Value2,
+    Neg(Box<Node>),
+    Binary(BinaryOp, Box<Node>, Box<Node>),
+    Abs(Box<Node>),
+    Len(Box<Node>),
+    Lower(Box<Node>),
+    // The type is that of both operands.
+    Compare(CompareOp, Type, Box<Node>, Box<Node>),
+    TextTest(TextOp, Box<Node>, Box<Node>),
+    Not(Box<Node>),
+    And(Box<Node>, Box<Node>),
+    Or(Box<Node>, Box<Node>),
+}
+
+impl Node {
+    // Only called on nodes the parser typed as numbers.
+    fn number(&self, record: &Record) -> f64 {
+        match self {
+            Node::Number(n) => *n,
+            Node::Id => record.id as f64,
+            Node::Value1 => record.value1 as f64,
+            Node::Value2 => record.value2,
+            Node::Neg(node) => -node.number(record),
+            Node::Binary(op, left, right) => {
+                let (a, b) = (left.number(record), right.number(record));
+                match op {
+                    BinaryOp::Add => a + b,
+                    BinaryOp::Sub => a - b,
+                    BinaryOp::Mul => a * b,
+                    BinaryOp::Div => a / b,
+                    BinaryOp::Rem => a % b,
+                }
+            }
+            Node::Abs(node) => node.number(record).abs(),
+            Node::Len(node) => node.text(record).chars().count() as f64,
+            _ => unreachable!("node evaluated as a number"),
+        }
+    }
+
+    // Only called on nodes the parser typed as text.
+    fn text<'a>(&'a self, record: &'a Record) -> Cow<'a, str> {
+        match self {
+            Node::Text(s) => Cow::Borrowed(s),
+            Node::Name => Cow::Borrowed(&record.name),
+            Node::Lower(node) => Cow::Owned(node.text(record).to_lowercase()),
+            _ => unreachable!("node evaluated as text"),
+        }
+    }
+
+    // Only called on nodes the parser typed as conditions.
+    fn truth(&self, record: &Record) -> bool {
+        match self {
+            Node::Compare(op, Type::Number, left, right) => {
+                op.holds(left.number(record).partial_cmp(&right.number(record)))
+            }
+            Node::Compare(op, _, left, right) => op.holds(Some(compare_str_lex(
+                &left.text(record),
+                &right.text(record),
+            ))),
+            Node::TextTest(op, left, right) => {
+                let (text, pattern) = (left.text(record), right.text(record));
+                match op {
+                    TextOp::StartsWith => text.starts_with(&*pattern),
+                    TextOp::EndsWith => text.ends_with(&*pattern),
+                    TextOp::Contains => text.contains(&*pattern),
+                }
+            }
+            Node::Not(node) => !node.truth(record),
+            Node::And(left, right) => left.truth(record) && right.truth(record),
+            Node::Or(left, right) => left.truth(record) || right.truth(record),
+            _ => unreachable!("node evaluated as a condition"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Expr {
+    root: Node,
+    ty: Type,
+}
+
+// Numbers compare with total_cmp, so NaN sorts after every other number.
+#[derive(Debug, Clone)]
+pub enum Value {
+    Number(f64),
+    Text(String),
+}
+
+impl Ord for Value {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Value::Number(a), Value::Number(b)) => a.total_cmp(b),
+            (Value::Text(a), Value::Text(b)) => compare_str_lex(a, b),
+            (Value::Number(_), Value::Text(_)) => Ordering::Less,
+            (Value::Text(_), Value::Number(_)) => Ordering::Greater,
+        }
+    }
+}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Value {}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Number(n) => write!(f, "{}", n),
+            Value::Text(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+impl Expr {
+    // A sort key: a number or text.
+    pub fn parse_key(source: &str) -> Result<Expr, String> {
+        let expr = Expr::parse(source)?;
+        if expr.ty == Type::Bool {
+            return Err("a sort key must be a number or text, not a condition".to_string());
+        }
+        Ok(expr)
+    }
+
+    pub fn parse_filter(source: &str) -> Result<Expr, String> {
+        let expr = Expr::parse(source)?;
+        if expr.ty != Type::Bool {
+            return Err(format!("a filter must be a condition, not {}", expr.ty));
+        }
+        Ok(expr)
+    }
+
+    fn parse(source: &str) -> Result<Expr, String> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            pos: 0,
+        };
+        let (root, ty) = parser.or()?;
+        match parser.peek() {
+            None => Ok(Expr { root, ty }),
+            Some(token) => Err(format!("unexpected {}", token)),
+        }
+    }
+
+    pub fn eval(&self, record: &Record) -> Value {
+        match self.ty {
+            Type::Number => {
+                // 0.0 / 0.0 gives a NaN with the sign bit set, which total_cmp
+                // would put before -inf; the positive NaN keeps NaN last.
+                let n = self.root.number(record);
+                Value::Number(if n.is_nan() { f64::NAN } else { n })
+            }
+            _ => Value::Text(self.root.text(record).into_owned()),
+        }
+    }
+
+    pub fn compare(&self, a: &Record, b: &Record) -> Ordering {
+        self.eval(a).cmp(&self.eval(b))
+    }
+
+    pub fn matches(&self, record: &Record) -> bool {
+        self.root.truth(record)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Text(String),
+    Ident(String),
+    Symbol(&'static str),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Number(n) => write!(f, "number {}", n),
+            Token::Text(s) => write!(f, "string {:?}", s),
+            Token::Ident(name) => write!(f, "'{}'", name),
+            Token::Symbol(symbol) => write!(f, "'{}'", symbol),
+        }
+    }
+}
+
+// Two-character symbols come first so that "<=" is not read as "<".
+const SYMBOLS: [&str; 16] = [
+    "<=", ">=", "==", "!=", "&&", "||", "<", ">", "!", "+", "-", "*", "/", "%", "(", ")",
+];
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = source.trim_start();
+
+    while let Some(c) = rest.chars().next() {
+        let length = if c.is_ascii_digit() || c == '.' {
+            let length = rest
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .unwrap_or(rest.len());
+            let text = &rest[..length];
+            let number = text
+                .parse()
+                .map_err(|_| format!("invalid number '{}'", text))?;
+            tokens.push(Token::Number(number));
+            length
+        } else if c.is_alphabetic() || c == '_' {
+            let length = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Ident(rest[..length].to_ascii_lowercase()));
+            length
+        } else if c == '"' {
+            let (text, length) = read_string(rest)?;
+            tokens.push(Token::Text(text));
+            length
+        } else if let Some(symbol) = SYMBOLS.into_iter().find(|symbol| rest.starts_with(symbol)) {
+            tokens.push(Token::Symbol(symbol));
+            symbol.len()
+        } else {
+            return Err(format!("unexpected character '{}'", c));
+        };
+        rest = rest[length..].trim_start();
+    }
+
+    Ok(tokens)
+}
+
+// Reads the string literal `rest` starts with, returning its contents and
+// length; backslash escapes the next character.
+fn read_string(rest: &str) -> Result<(String, usize), String> {
+    let mut text = String::new();
+    let mut chars = rest.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Ok((text, i + 1)),
+            '\\' => match chars.next() {
+                Some((_, escaped)) => text.push(escaped),
+                None => break,
+            },
+            c => text.push(c),
+        }
+    }
+    Err("unterminated string".to_string())
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, symbol: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, symbol: &str) -> Result<(), String> {
+        if self.eat(symbol) {
+            Ok(())
+        } else {
+            match self.peek() {
+                Some(token) => Err(format!("expected '{}', found {}", symbol, token)),
+                None => Err(format!("expected '{}' at the end", symbol)),
+            }
+        }
+    }
+
+    fn or(&mut self) -> Result<(Node, Type), String> {
+        let mut left = self.and()?;
+        while self.eat("||") {
+            let right = self.and()?;
+            expect_type(left.1, Type::Bool, "'||'")?;
+            expect_type(right.1, Type::Bool, "'||'")?;
+            left = (Node::Or(Box::new(left.0), Box::new(right.0)), Type::Bool);
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<(Node, Type), String> {
+        let mut left = self.not()?;
+        while self.eat("&&") {
+            let right = self.not()?;
+            expect_type(left.1, Type::Bool, "'&&'")?;
+            expect_type(right.1, Type::Bool, "'&&'")?;
+            left = (Node::And(Box::new(left.0), Box::new(right.0)), Type::Bool);
+        }
+        Ok(left)
+    }
+
+    fn not(&mut self) -> Result<(Node, Type), String> {
+        if self.eat("!") {
+            let (node, ty) = self.not()?;
+            expect_type(ty, Type::Bool, "'!'")?;
+            return Ok((Node::Not(Box::new(node)), Type::Bool));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<(Node, Type), String> {
+        let left = self.sum()?;
+        let compare_op = [
+            ("<", CompareOp::Less),
+            ("<=", CompareOp::LessEqual),
+            (">", CompareOp::Greater),
+            (">=", CompareOp::GreaterEqual),
+            ("==", CompareOp::Equal),
+            ("!=", CompareOp::NotEqual),
+        ]
+        .into_iter()
+        .find(|(symbol, _)| self.eat(symbol));
+        if let Some((symbol, op)) = compare_op {
+            let right = self.sum()?;
+            let context = format!("'{}'", symbol);
+            if left.1 == Type::Bool {
+                return Err(format!("{} expects a number or text", context));
+            }
+            expect_type(right.1, left.1, &context)?;
+            return Ok((
+                Node::Compare(op, left.1, Box::new(left.0), Box::new(right.0)),
+                Type::Bool,
+            ));
+        }
+
+        let text_op = match self.peek() {
+            Some(Token::Ident(name)) if name == "starts_with" => Some(TextOp::StartsWith),
+            Some(Token::Ident(name)) if name == "ends_with" => Some(TextOp::EndsWith),
+            Some(Token::Ident(name)) if name == "contains" => Some(TextOp::Contains),
+            _ => None,
+        };
+        if let Some(op) = text_op {
+            let context = format!("{}", self.next().unwrap());
+            let right = self.sum()?;
+            expect_type(left.1, Type::Text, &context)?;
+            expect_type(right.1, Type::Text, &context)?;
+            return Ok((
+                Node::TextTest(op, Box::new(left.0), Box::new(right.0)),
+                Type::Bool,
+            ));
+        }
+
+        Ok(left)
+    }
+
+    fn sum(&mut self) -> Result<(Node, Type), String> {
+        let mut left = self.term()?;
+        loop {
+            let op = if self.eat("+") {
+                BinaryOp::Add
+            } else if self.eat("-") {
+                BinaryOp::Sub
+            } else {
+                return Ok(left);
+            };
+            let right = self.term()?;
+            left = binary(op, left, right)?;
+        }
+    }
+
+    fn term(&mut self) -> Result<(Node, Type), String> {
+        let mut left = self.unary()?;
+        loop {
+            let op = if self.eat("*") {
+                BinaryOp::Mul
+            } else if self.eat("/") {
+                BinaryOp::Div
+            } else if self.eat("%") {
+                BinaryOp::Rem
+            } else {
+                return Ok(left);
+            };
+            let right = self.unary()?;
+            left = binary(op, left, right)?;
+        }
+    }
+
+    fn unary(&mut self) -> Result<(Node, Type), String> {
+        if self.eat("-") {
+            let (node, ty) = self.unary()?;
+            expect_type(ty, Type::Number, "'-'")?;
+            return Ok((Node::Neg(Box::new(node)), Type::Number));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<(Node, Type), String> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok((Node::Number(n), Type::Number)),
+            Some(Token::Text(s)) => Ok((Node::Text(s), Type::Text)),
+            Some(Token::Symbol("(")) => {
+                let inner = self.or()?;
+                self.expect(")")?;
+                Ok(inner)
+            }
+            Some(Token::Ident(name)) if self.eat("(") => {
+                let (arg, ty) = self.or()?;
+                self.expect(")")?;
+                let arg = Box::new(arg);
+                match name.as_str() {
+                    "abs" => {
+                        expect_type(ty, Type::Number, "abs")?;
+                        Ok((Node::Abs(arg), Type::Number))
+                    }
+                    "len" => {
+                        expect_type(ty, Type::Text, "len")?;
+                        Ok((Node::Len(arg), Type::Number))
+                    }
+                    "lower" => {
+                        expect_type(ty, Type::Text, "lower")?;
+                        Ok((Node::Lower(arg), Type::Text))
+                    }
+                    _ => Err(format!("unknown function '{}'", name)),
+                }
+            }
+            Some(Token::Ident(name)) => match name.as_str() {
+                "id" => Ok((Node::Id, Type::Number)),
+                "name" => Ok((Node::Name, Type::Text)),
+                "value1" => Ok((Node::Value1, Type::Number)),
+                "value2" => Ok((Node::Value2, Type::Number)),
+                _ => Err(format!("unknown column '{}'", name)),
+            },
+            Some(token) => Err(format!("unexpected {}", token)),
+            None => Err("unexpected end of expression".to_string()),
+        }
+    }
+}
+
+fn binary(op: BinaryOp, left: (Node, Type), right: (Node, Type)) -> Result<(Node, Type), String> {
+    let symbol = match op {
+        BinaryOp::Add => "'+'",
+        BinaryOp::Sub => "'-'",
+        BinaryOp::Mul => "'*'",
+        BinaryOp::Div => "'/'",
+        BinaryOp::Rem => "'%'",
+    };
+    expect_type(left.1, Type::Number, symbol)?;
+    expect_type(right.1, Type::Number, symbol)?;
+    Ok((
+        Node::Binary(op, Box::new(left.0), Box::new(right.0)),
+        Type::Number,
+    ))
+}
+
+fn expect_type(found: Type, expected: Type, context: &str) -> Result<(), String> {
+    if found == expected {
+        Ok(())
+    } else {
+        Err(format!("{} expects {}, not {}", context, expected, found))
+    }
+}
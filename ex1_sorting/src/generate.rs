@@ -0,0 +1,60 @@
+use crate::records::Record;
+
+// xorshift64*: small, seedable and good enough for generating test inputs.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed.max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    // Uniform in [0, bound); bound must be non-zero.
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+}
+
+pub const DISTRIBUTIONS: [&str; 4] = ["Sorted", "Nearly Sorted", "Reversed", "Random"];
+
+// `disorder` is the fraction of elements displaced by random swaps in the
+// nearly sorted distribution.
+pub fn generate_i64(distribution: &str, size: usize, disorder: f64, seed: u64) -> Vec<i64> {
+    let mut rng = Rng::new(seed);
+    match distribution {
+        "Sorted" => (0..size as i64).collect(),
+        "Nearly Sorted" => {
+            let mut data: Vec<i64> = (0..size as i64).collect();
+            let swaps = (size as f64 * disorder / 2.0) as usize;
+            for _ in 0..swaps {
+                let a = rng.below(size);
+                let b = rng.below(size);
+                data.swap(a, b);
+            }
+            data
+        }
+        "Reversed" => (0..size as i64).rev().collect(),
+        _ => (0..size).map(|_| rng.next_u64() as i64).collect(),
+    }
+}
+
+// Random records with few distinct names, so that sorting by Name has ties.
+pub fn generate_records(size: usize, seed: u64) -> Vec<Record> {
+    let mut rng = Rng::new(seed);
+    let distinct_names = (size / 4).max(1);
+    (0..size)
+        .map(|id| Record {
+            id: id as i64,
+            name: format!("name{}", rng.below(distinct_names)).as_str().into(),
+            value1: rng.below(2_000_001) as i64 - 1_000_000,
+            value2: rng.next_u64() as f64 / u64::MAX as f64 * 1_000_000.0,
+            line_range: (0, 0),
+        })
+        .collect()
+}
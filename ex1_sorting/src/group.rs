@@ -0,0 +1,163 @@
+use std::cmp::Ordering;
+use std::io::Write;
+
+use crate::output::{push_csv_field, push_json_string, OutputFormat, OutputSpec};
+use crate::records::Record;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Keep {
+    First,
+    Last,
+}
+
+impl Keep {
+    pub fn parse(name: &str) -> Option<Keep> {
+        match name.to_ascii_lowercase().as_str() {
+            "first" => Some(Keep::First),
+            "last" => Some(Keep::Last),
+            _ => None,
+        }
+    }
+}
+
+// Keeps one record per run of equal keys in already sorted records. First
+// and last are in input order only if the sort was stable.
+pub fn dedup_sorted<F>(records: &mut Vec<Record>, cmp: F, keep: Keep)
+where
+    F: Fn(&Record, &Record) -> Ordering,
+{
+    match keep {
+        Keep::First => records.dedup_by(|next, kept| cmp(kept, next) == Ordering::Equal),
+        Keep::Last => {
+            records.reverse();
+            records.dedup_by(|next, kept| cmp(kept, next) == Ordering::Equal);
+            records.reverse();
+        }
+    }
+}
+
+pub struct Group {
+    pub key: String,
+    pub count: usize,
+    pub value1: (i64, i64, f64),
+    // Min, max and mean over the non-NaN values, NaN if there are none.
+    pub value2: (f64, f64, f64),
+}
+
+// `format_key` renders the key shared by the records of a group.
+pub fn group_sorted<F, K>(records: &[Record], cmp: F, format_key: K) -> Vec<Group>
+where
+    F: Fn(&Record, &Record) -> Ordering,
+    K: Fn(&Record) -> String,
+{
+    records
+        .chunk_by(|a, b| cmp(a, b) == Ordering::Equal)
+        .map(|run| {
+            let mut value1 = (i64::MAX, i64::MIN, 0.0);
+            let mut value2 = (f64::NAN, f64::NAN, 0.0);
+            let mut finite = 0;
+            for record in run {
+                value1.0 = value1.0.min(record.value1);
+                value1.1 = value1.1.max(record.value1);
+                value1.2 += record.value1 as f64;
+                if !record.value2.is_nan() {
+                    value2.0 = value2.0.min(record.value2);
+                    value2.1 = value2.1.max(record.value2);
+                    value2.2 += record.value2;
+                    finite += 1;
+                }
+            }
+            value1.2 /= run.len() as f64;
+            value2.2 = if finite > 0 {
+                value2.2 / finite as f64
+            } else {
+                f64::NAN
+            };
+
+            Group {
+                key: format_key(&run[0]),
+                count: run.len(),
+                value1,
+                value2,
+            }
+        })
+        .collect()
+}
+
+const GROUP_FIELDS: [&str; 8] = [
+    "key",
+    "count",
+    "value1_min",
+    "value1_max",
+    "value1_mean",
+    "value2_min",
+    "value2_max",
+    "value2_mean",
+];
+
+// CSV output starts with a header line naming the fields; JSON Lines uses
+// the same names as keys.
+pub fn write_groups<W: Write>(
+    mut writer: W,
+    groups: &[Group],
+    spec: &OutputSpec,
+) -> std::io::Result<()> {
+    let json = spec.format == OutputFormat::JsonLines;
+    let delimiter = [spec.delimiter.unwrap_or(b',')];
+    let separator: &[u8] = if json { b"," } else { &delimiter };
+
+    if !json {
+        writer.write_all(
+            GROUP_FIELDS
+                .join(&(delimiter[0] as char).to_string())
+                .as_bytes(),
+        )?;
+        writer.write_all(b"\n")?;
+    }
+
+    let mut buffer = Vec::with_capacity(256);
+    for group in groups {
+        buffer.clear();
+        let fields = [
+            group.count.to_string(),
+            group.value1.0.to_string(),
+            group.value1.1.to_string(),
+            format_float(group.value1.2, spec.precision, json),
+            format_float(group.value2.0, spec.precision, json),
+            format_float(group.value2.1, spec.precision, json),
+            format_float(group.value2.2, spec.precision, json),
+        ];
+
+        if json {
+            buffer.push(b'{');
+            push_json_string(&mut buffer, GROUP_FIELDS[0]);
+            buffer.push(b':');
+            push_json_string(&mut buffer, &group.key);
+        } else {
+            push_csv_field(&mut buffer, &group.key, delimiter[0]);
+        }
+        for (name, value) in GROUP_FIELDS[1..].iter().zip(&fields) {
+            buffer.extend_from_slice(separator);
+            if json {
+                push_json_string(&mut buffer, name);
+                buffer.push(b':');
+            }
+            buffer.extend_from_slice(value.as_bytes());
+        }
+        if json {
+            buffer.push(b'}');
+        }
+        buffer.push(b'\n');
+        writer.write_all(&buffer)?;
+    }
+
+    writer.flush()
+}
+
+fn format_float(value: f64, precision: Option<usize>, json: bool) -> String {
+    match precision {
+        _ if json && !value.is_finite() => "null".to_string(),
+        Some(precision) => format!("{:.*}", precision, value),
+        None => value.to_string(),
+    }
+}
@@ -0,0 +1,61 @@
+use std::cmp::Ordering;
+
+use crate::deadline::{Cancelled, WorkCounter};
+use crate::quicksort::SortConfig;
+
+pub fn heap_sort<T, F>(arr: &mut [T], compar: &F)
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    // Without a deadline the sort always completes.
+    let _ = heap_sort_with_config(arr, compar, &SortConfig::default());
+}
+
+// Only the deadline and progress of the config apply to heap sort.
+pub fn heap_sort_with_config<T, F>(
+    arr: &mut [T],
+    compar: &F,
+    config: &SortConfig,
+) -> Result<(), Cancelled>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    let len = arr.len();
+    let mut work = WorkCounter::default();
+    for root in (0..len / 2).rev() {
+        sift_down(arr, root, len, compar);
+        if work.add(1) {
+            config.check_deadline()?;
+        }
+    }
+    for end in (1..len).rev() {
+        arr.swap(0, end);
+        sift_down(arr, 0, end, compar);
+        config.report_progress(1);
+        if work.add(1) {
+            config.check_deadline()?;
+        }
+    }
+    config.report_progress(len.min(1));
+    Ok(())
+}
+
+fn sift_down<T, F>(arr: &mut [T], mut root: usize, end: usize, compar: &F)
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    loop {
+        let mut child = 2 * root + 1;
+        if child >= end {
+            break;
+        }
+        if child + 1 < end && compar(&arr[child], &arr[child + 1]) == Ordering::Less {
+            child += 1;
+        }
+        if compar(&arr[root], &arr[child]) != Ordering::Less {
+            break;
+        }
+        arr.swap(root, child);
+        root = child;
+    }
+}
@@ -0,0 +1,54 @@
+use std::cmp::Ordering;
+
+use crate::deadline::{Cancelled, WorkCounter};
+use crate::quicksort::SortConfig;
+
+pub fn binary_insertion_sort<T, F>(arr: &mut [T], compar: &F)
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    binary_insertion_sort_from(arr, 1, compar);
+}
+
+// Only the deadline and progress of the config apply. Inserting moves up to
+// every element already sorted, so the deadline is checked by work done.
+pub fn binary_insertion_sort_with_config<T, F>(
+    arr: &mut [T],
+    compar: &F,
+    config: &SortConfig,
+) -> Result<(), Cancelled>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    let mut work = WorkCounter::default();
+    for i in 1..arr.len() {
+        let pos = insert(arr, i, compar);
+        config.report_progress(1);
+        if work.add(i - pos + 1) {
+            config.check_deadline()?;
+        }
+    }
+    config.report_progress(arr.len().min(1));
+    Ok(())
+}
+
+// Sorts arr assuming arr[..sorted] is already sorted. Each element goes after
+// the equal ones already placed, so the sort is stable.
+pub fn binary_insertion_sort_from<T, F>(arr: &mut [T], sorted: usize, compar: &F)
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    for i in sorted.max(1)..arr.len() {
+        insert(arr, i, compar);
+    }
+}
+
+// Moves arr[i] into the sorted arr[..i], returning where it went.
+fn insert<T, F>(arr: &mut [T], i: usize, compar: &F) -> usize
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    let pos = arr[..i].partition_point(|x| compar(x, &arr[i]) != Ordering::Greater);
+    arr[pos..=i].rotate_right(1);
+    pos
+}
@@ -1,44 +1,69 @@
-use crate::records::run_sorting_with_records;
-use crate::test::*;
-use inquire::Select;
+use crate::error::SortError;
+use crate::records::{run_sorting_with_records, run_statistics_with_records};
+use crate::sorter::sorter_names;
+use crate::test::{run_sorting_test, DATA_TYPES};
+use inquire::{Select, Text};
 
+mod adaptivemergesort;
+mod bench;
+mod cache;
+mod cachedkey;
+mod check;
+mod cli;
 mod compar;
+mod deadline;
+mod error;
+mod expr;
+mod generate;
+mod group;
+mod heapsort;
+mod insertionsort;
+mod memory;
+mod merge;
 mod mergesort;
+mod output;
+mod progress;
 mod quicksort;
 mod records;
+mod report;
+mod shellsort;
+mod sorter;
 mod test;
 
 pub fn main() {
-    let data_source_choice = Select::new("Choose data source", vec!["Test", "Records"])
-        .prompt()
-        .unwrap();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = if args.is_empty() {
+        run_interactive()
+    } else {
+        cli::run(&args)
+    };
 
-    let algorithm_choice =
-        Select::new("Choose sorting algorithm", vec!["Merge Sort", "Quick Sort"])
-            .prompt()
-            .unwrap();
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run_interactive() -> Result<(), SortError> {
+    let data_source_choice =
+        Select::new("Choose data source", vec!["Test", "Records", "Statistics"]).prompt()?;
+
+    if data_source_choice == "Statistics" {
+        return run_statistics_with_records();
+    }
+
+    let algorithm_choice = Select::new("Choose sorting algorithm", sorter_names()).prompt()?;
 
     match data_source_choice {
         "Test" => {
-            let test_case_choice =
-                Select::new("Choose test case", vec!["Test 1", "Test 2", "Test 3"])
-                    .prompt()
-                    .unwrap();
-
-            let data_type_choice = Select::new("Choose data type", vec!["i32", "f32", "String"])
-                .prompt()
-                .unwrap();
-
-            match data_type_choice {
-                "i32" => run_sorting_test_i32(algorithm_choice, test_case_choice),
-                "f32" => run_sorting_test_f32(algorithm_choice, test_case_choice),
-                "String" => run_sorting_test_string(algorithm_choice, test_case_choice),
-                _ => println!("Invalid data type selected."),
-            }
-        }
-        "Records" => {
-            let _ = run_sorting_with_records(algorithm_choice);
+            let data_type_choice = Select::new("Choose data type", DATA_TYPES.to_vec()).prompt()?;
+            let path = Text::new("Values file")
+                .with_default("rsrc/values.txt")
+                .prompt()?;
+
+            run_sorting_test(algorithm_choice, data_type_choice, &path)
         }
-        _ => println!("Invalid data source selected."),
+        "Records" => run_sorting_with_records(algorithm_choice),
+        other => Err(SortError::Usage(format!("invalid data source '{}'", other))),
     }
 }
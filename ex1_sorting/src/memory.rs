@@ -0,0 +1,161 @@
+// Per-phase memory accounting. Peak RSS comes from /proc on Linux; byte-level
+// allocation counts need the `count-alloc` feature, which installs a counting
+// global allocator.
+
+#[cfg(feature = "count-alloc")]
+mod counting {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    pub static LIVE: AtomicUsize = AtomicUsize::new(0);
+    pub static PEAK: AtomicUsize = AtomicUsize::new(0);
+    pub static TOTAL: AtomicUsize = AtomicUsize::new(0);
+
+    pub struct CountingAllocator;
+
+    fn record_alloc(size: usize) {
+        let live = LIVE.fetch_add(size, Ordering::Relaxed) + size;
+        PEAK.fetch_max(live, Ordering::Relaxed);
+        TOTAL.fetch_add(size, Ordering::Relaxed);
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let ptr = System.alloc(layout);
+            if !ptr.is_null() {
+                record_alloc(layout.size());
+            }
+            ptr
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            let ptr = System.alloc_zeroed(layout);
+            if !ptr.is_null() {
+                record_alloc(layout.size());
+            }
+            ptr
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout);
+            LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let new_ptr = System.realloc(ptr, layout, new_size);
+            if !new_ptr.is_null() {
+                LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+                record_alloc(new_size);
+            }
+            new_ptr
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+}
+
+pub struct PhaseMemory {
+    #[cfg(feature = "count-alloc")]
+    live_at_start: usize,
+    #[cfg(feature = "count-alloc")]
+    total_at_start: usize,
+}
+
+impl PhaseMemory {
+    pub fn start() -> Self {
+        reset_peak_rss();
+
+        #[cfg(feature = "count-alloc")]
+        {
+            use std::sync::atomic::Ordering;
+            let live = counting::LIVE.load(Ordering::Relaxed);
+            counting::PEAK.store(live, Ordering::Relaxed);
+            PhaseMemory {
+                live_at_start: live,
+                total_at_start: counting::TOTAL.load(Ordering::Relaxed),
+            }
+        }
+        #[cfg(not(feature = "count-alloc"))]
+        PhaseMemory {}
+    }
+
+    pub fn finish(&self) -> MemoryUsage {
+        #[cfg(feature = "count-alloc")]
+        {
+            use std::sync::atomic::Ordering;
+            let total = counting::TOTAL.load(Ordering::Relaxed);
+            let peak = counting::PEAK.load(Ordering::Relaxed);
+            MemoryUsage {
+                peak_rss: peak_rss(),
+                allocated: Some(total - self.total_at_start),
+                peak_heap: Some(peak.saturating_sub(self.live_at_start)),
+            }
+        }
+        #[cfg(not(feature = "count-alloc"))]
+        MemoryUsage {
+            peak_rss: peak_rss(),
+            allocated: None,
+            peak_heap: None,
+        }
+    }
+}
+
+pub struct MemoryUsage {
+    pub peak_rss: Option<usize>,
+    // Bytes allocated during the phase; needs `count-alloc`.
+    pub allocated: Option<usize>,
+    // Growth of live heap bytes at the phase's high-water mark; needs `count-alloc`.
+    pub peak_heap: Option<usize>,
+}
+
+impl MemoryUsage {
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(peak) = self.peak_rss {
+            parts.push(format!("peak RSS {}", format_bytes(peak)));
+        }
+        if let (Some(allocated), Some(peak)) = (self.allocated, self.peak_heap) {
+            parts.push(format!(
+                "allocated {}, peak heap +{}",
+                format_bytes(allocated),
+                format_bytes(peak)
+            ));
+        }
+
+        if parts.is_empty() {
+            "unavailable".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+}
+
+// Writing 5 to clear_refs resets the VmHWM high-water mark, so each phase
+// reports its own peak instead of the process-wide one.
+fn reset_peak_rss() {
+    #[cfg(target_os = "linux")]
+    let _ = std::fs::write("/proc/self/clear_refs", "5");
+}
+
+fn peak_rss() -> Option<usize> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kib: usize = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+pub fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
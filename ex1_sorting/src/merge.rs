@@ -0,0 +1,199 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::time::Instant;
+
+use crate::error::SortError;
+use crate::records::{
+    column_comparator, detect_delimiter, is_same_file, parse_record, write_output, Record,
+};
+
+type Compar = fn(&Record, &Record) -> Ordering;
+
+struct Source<'a> {
+    reader: Box<dyn BufRead + 'a>,
+    path: &'a str,
+    delimiter: u8,
+    line_number: usize,
+}
+
+impl Source<'_> {
+    // Reads the next non-empty line into `line`, returning its record, or None
+    // at the end of the file.
+    fn next_record(&mut self, line: &mut Vec<u8>) -> Result<Option<Record>, SortError> {
+        loop {
+            line.clear();
+            let read = self
+                .reader
+                .read_until(b'\n', line)
+                .map_err(SortError::io(self.path))?;
+            if read == 0 {
+                return Ok(None);
+            }
+            self.line_number += 1;
+            if line.last() == Some(&b'\n') {
+                line.pop();
+            }
+            if line.is_empty() {
+                continue;
+            }
+
+            return parse_record(line, 0, self.delimiter)
+                .map(Some)
+                .map_err(|message| SortError::Parse {
+                    path: self.path.to_string(),
+                    line: self.line_number,
+                    message,
+                });
+        }
+    }
+}
+
+// The smallest record of one source. BinaryHeap is a max-heap, so the
+// ordering is reversed; ties go to the earlier source to keep the merge stable.
+struct Head {
+    record: Record,
+    line: Vec<u8>,
+    source: usize,
+    cmp: Compar,
+}
+
+impl Ord for Head {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.cmp)(&other.record, &self.record).then(other.source.cmp(&self.source))
+    }
+}
+
+impl PartialOrd for Head {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Head {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Head {}
+
+pub fn run_merge(
+    inputs: &[String],
+    output: &str,
+    column: &str,
+    delimiter: Option<u8>,
+) -> Result<(), SortError> {
+    let cmp =
+        column_comparator(column).ok_or_else(|| SortError::InvalidColumn(column.to_string()))?;
+    if let Some(input) = inputs.iter().find(|input| is_same_file(input, output)) {
+        return Err(SortError::OutputIsInput(input.to_string()));
+    }
+    if inputs.iter().filter(|input| *input == "-").count() > 1 {
+        return Err(SortError::Usage(
+            "standard input can only be merged once".to_string(),
+        ));
+    }
+
+    let start = Instant::now();
+    let mut sources = inputs
+        .iter()
+        .map(|path| open_source(path, delimiter))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // The writer only reports I/O errors, so a failing input is kept aside and
+    // the write is aborted to leave no partial output behind.
+    let mut merged = Ok(0);
+    let written = write_output(output, |writer| {
+        merged = merge_sources(&mut sources, writer, cmp, output);
+        match merged {
+            Ok(_) => Ok(()),
+            Err(_) => Err(io::Error::other("merge aborted")),
+        }
+    });
+    let records = merged?;
+    written?;
+
+    let message = format!(
+        "Merged {} records from {} files in {:.4?}",
+        records,
+        inputs.len(),
+        start.elapsed()
+    );
+    if output == "-" {
+        eprintln!("{}", message);
+    } else {
+        println!("{}", message);
+    }
+    Ok(())
+}
+
+fn open_source(path: &str, delimiter: Option<u8>) -> Result<Source<'_>, SortError> {
+    let mut reader: Box<dyn BufRead> = if path == "-" {
+        Box::new(io::stdin().lock())
+    } else {
+        Box::new(BufReader::new(
+            File::open(path).map_err(SortError::io(path))?,
+        ))
+    };
+    let delimiter = match delimiter {
+        Some(delimiter) => delimiter,
+        None => detect_delimiter(reader.fill_buf().map_err(SortError::io(path))?),
+    };
+
+    Ok(Source {
+        reader,
+        path,
+        delimiter,
+        line_number: 0,
+    })
+}
+
+// Writes the raw lines of all sources in order, checking along the way that
+// every source is itself sorted.
+fn merge_sources(
+    sources: &mut [Source],
+    writer: &mut dyn Write,
+    cmp: Compar,
+    output: &str,
+) -> Result<usize, SortError> {
+    let mut heap = BinaryHeap::with_capacity(sources.len());
+    for (index, source) in sources.iter_mut().enumerate() {
+        let mut line = Vec::with_capacity(256);
+        if let Some(record) = source.next_record(&mut line)? {
+            heap.push(Head {
+                record,
+                line,
+                source: index,
+                cmp,
+            });
+        }
+    }
+
+    let mut count = 0;
+    while let Some(mut head) = heap.pop() {
+        writer
+            .write_all(&head.line)
+            .and_then(|_| writer.write_all(b"\n"))
+            .map_err(SortError::io(output))?;
+        count += 1;
+
+        let source = &mut sources[head.source];
+        let previous = head.record;
+        let Some(record) = source.next_record(&mut head.line)? else {
+            continue;
+        };
+        if cmp(&previous, &record) == Ordering::Greater {
+            return Err(SortError::Unsorted {
+                path: source.path.to_string(),
+                line: source.line_number,
+            });
+        }
+        head.record = record;
+        heap.push(head);
+    }
+
+    writer.flush().map_err(SortError::io(output))?;
+    Ok(count)
+}
@@ -1,23 +1,120 @@
 use std::cmp::Ordering;
 
+use crate::deadline::{Cancelled, WorkCounter, CHECK_MIN_LEN};
+use crate::quicksort::SortConfig;
+
 pub fn merge_sort<T, F>(base: &mut [T], compar: &F)
+where
+    T: Clone,
+    F: Fn(&T, &T) -> Ordering,
+{
+    // Without a deadline the sort always completes.
+    let _ = merge_sort_recursive(base, compar, None);
+}
+
+// Only the deadline and progress of the config apply to merge sort.
+pub fn merge_sort_with_config<T, F>(
+    base: &mut [T],
+    compar: &F,
+    config: &SortConfig,
+) -> Result<(), Cancelled>
+where
+    T: Clone,
+    F: Fn(&T, &T) -> Ordering,
+{
+    if let Some(progress) = &config.progress {
+        progress.set_work(base.len() * merge_levels(base.len()));
+    }
+    merge_sort_recursive(base, compar, Some(config))
+}
+
+fn merge_sort_recursive<T, F>(
+    base: &mut [T],
+    compar: &F,
+    config: Option<&SortConfig>,
+) -> Result<(), Cancelled>
 where
     T: Clone,
     F: Fn(&T, &T) -> Ordering,
 {
     if base.len() <= 1 {
-        return;
+        return Ok(());
+    }
+    if let Some(config) = config {
+        // Small ranges are sorted without checks and reported as a whole.
+        if base.len() < CHECK_MIN_LEN {
+            merge_sort_recursive(base, compar, None)?;
+            config.report_progress(base.len() * merge_levels(base.len()));
+            return Ok(());
+        }
+        config.check_deadline()?;
     }
 
     let mid = base.len() / 2;
     let (l, r) = base.split_at_mut(mid);
 
-    merge_sort(l, compar);
-    merge_sort(r, compar);
+    merge_sort_recursive(l, compar, config)?;
+    merge_sort_recursive(r, compar, config)?;
 
     let l_clone = l.to_vec();
     let r_clone = r.to_vec();
     merge(base, &l_clone, &r_clone, compar);
+    if let Some(config) = config {
+        config.report_progress(base.len());
+    }
+    Ok(())
+}
+
+// Depth of the recursion, each level of which copies every element once.
+fn merge_levels(len: usize) -> usize {
+    (usize::BITS - len.saturating_sub(1).leading_zeros()) as usize
+}
+
+pub fn bottom_up_merge_sort<T, F>(base: &mut [T], compar: &F)
+where
+    T: Clone,
+    F: Fn(&T, &T) -> Ordering,
+{
+    // Without a deadline the sort always completes.
+    let _ = bottom_up_merge_sort_with_config(base, compar, &SortConfig::default());
+}
+
+// Only the deadline and progress of the config apply.
+pub fn bottom_up_merge_sort_with_config<T, F>(
+    base: &mut [T],
+    compar: &F,
+    config: &SortConfig,
+) -> Result<(), Cancelled>
+where
+    T: Clone,
+    F: Fn(&T, &T) -> Ordering,
+{
+    let len = base.len();
+    if let Some(progress) = &config.progress {
+        progress.set_work(len * merge_levels(len));
+    }
+    let mut width = 1;
+    let mut work = WorkCounter::default();
+
+    while width < len {
+        let mut start = 0;
+        while start + width < len {
+            let mid = start + width;
+            let end = (mid + width).min(len);
+
+            let l_clone = base[start..mid].to_vec();
+            let r_clone = base[mid..end].to_vec();
+            merge(&mut base[start..end], &l_clone, &r_clone, compar);
+            config.report_progress(end - start);
+            if work.add(end - start) {
+                config.check_deadline()?;
+            }
+
+            start = end;
+        }
+        width *= 2;
+    }
+    Ok(())
 }
 
 fn merge<T, F>(base: &mut [T], l: &[T], r: &[T], compar: &F)
@@ -30,7 +127,7 @@ where
     let mut k = 0;
 
     while i < l.len() && j < r.len() {
-        if compar(&l[i], &r[j]) == Ordering::Less {
+        if compar(&l[i], &r[j]) != Ordering::Greater {
             base[k] = l[i].clone();
             i += 1;
         } else {
@@ -0,0 +1,154 @@
+use std::io::Write;
+
+use crate::error::SortError;
+use crate::records::Record;
+
+pub const OUTPUT_COLUMNS: [&str; 4] = ["Id", "Name", "Value1", "Value2"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    // Original line bytes, copied verbatim from the input.
+    Raw,
+    Csv,
+    JsonLines,
+}
+
+#[derive(Debug, Clone)]
+pub struct OutputSpec {
+    pub format: OutputFormat,
+    pub columns: Vec<&'static str>,
+    pub precision: Option<usize>,
+    // Delimiter for rendered CSV; the input's delimiter if None.
+    pub delimiter: Option<u8>,
+}
+
+impl Default for OutputSpec {
+    fn default() -> Self {
+        OutputSpec {
+            format: OutputFormat::Raw,
+            columns: OUTPUT_COLUMNS.to_vec(),
+            precision: None,
+            delimiter: None,
+        }
+    }
+}
+
+impl OutputSpec {
+    pub fn parse_format(name: &str) -> Result<OutputFormat, SortError> {
+        match name.to_ascii_lowercase().as_str() {
+            "raw" => Ok(OutputFormat::Raw),
+            "csv" => Ok(OutputFormat::Csv),
+            "jsonl" | "json" => Ok(OutputFormat::JsonLines),
+            _ => Err(SortError::Usage(format!(
+                "unknown output format '{}'",
+                name
+            ))),
+        }
+    }
+
+    pub fn parse_columns(list: &str) -> Result<Vec<&'static str>, SortError> {
+        list.split(',')
+            .map(|name| {
+                OUTPUT_COLUMNS
+                    .into_iter()
+                    .find(|column| column.eq_ignore_ascii_case(name.trim()))
+                    .ok_or_else(|| SortError::InvalidColumn(name.to_string()))
+            })
+            .collect()
+    }
+}
+
+pub fn render_record(buffer: &mut Vec<u8>, record: &Record, bytes: &[u8], spec: &OutputSpec) {
+    match spec.format {
+        OutputFormat::Raw => {
+            buffer.extend_from_slice(&bytes[record.line_range.0..record.line_range.1]);
+        }
+        OutputFormat::Csv => {
+            let delimiter = spec.delimiter.unwrap_or(b',');
+            for (i, column) in spec.columns.iter().enumerate() {
+                if i > 0 {
+                    buffer.push(delimiter);
+                }
+                if *column == "Name" {
+                    push_csv_field(buffer, &record.name, delimiter);
+                } else {
+                    render_field(buffer, record, column, spec.precision, false);
+                }
+            }
+        }
+        OutputFormat::JsonLines => {
+            buffer.push(b'{');
+            for (i, column) in spec.columns.iter().enumerate() {
+                if i > 0 {
+                    buffer.push(b',');
+                }
+                push_json_string(buffer, &column.to_ascii_lowercase());
+                buffer.push(b':');
+                render_field(buffer, record, column, spec.precision, true);
+            }
+            buffer.push(b'}');
+        }
+    }
+    buffer.push(b'\n');
+}
+
+fn render_field(
+    buffer: &mut Vec<u8>,
+    record: &Record,
+    column: &str,
+    precision: Option<usize>,
+    json: bool,
+) {
+    // Writing into a Vec<u8> cannot fail.
+    match column {
+        "Id" => write!(buffer, "{}", record.id).unwrap(),
+        // render_record quotes CSV names itself, as that needs the delimiter.
+        "Name" => push_json_string(buffer, &record.name),
+        "Value1" => write!(buffer, "{}", record.value1).unwrap(),
+        _ if json && !record.value2.is_finite() => buffer.extend_from_slice(b"null"),
+        _ => match precision {
+            Some(precision) => write!(buffer, "{:.*}", precision, record.value2).unwrap(),
+            None => write!(buffer, "{}", record.value2).unwrap(),
+        },
+    }
+}
+
+// Quotes the field as RFC 4180 does when it holds the delimiter, a quote or
+// a line break, doubling the quotes inside.
+pub fn push_csv_field(buffer: &mut Vec<u8>, s: &str, delimiter: u8) {
+    let bytes = s.as_bytes();
+    if !bytes
+        .iter()
+        .any(|&b| b == delimiter || b == b'"' || b == b'\n' || b == b'\r')
+    {
+        buffer.extend_from_slice(bytes);
+        return;
+    }
+    buffer.push(b'"');
+    for &b in bytes {
+        if b == b'"' {
+            buffer.push(b'"');
+        }
+        buffer.push(b);
+    }
+    buffer.push(b'"');
+}
+
+pub fn push_json_string(buffer: &mut Vec<u8>, s: &str) {
+    buffer.push(b'"');
+    for c in s.chars() {
+        match c {
+            '"' => buffer.extend_from_slice(b"\\\""),
+            '\\' => buffer.extend_from_slice(b"\\\\"),
+            '\n' => buffer.extend_from_slice(b"\\n"),
+            '\r' => buffer.extend_from_slice(b"\\r"),
+            '\t' => buffer.extend_from_slice(b"\\t"),
+            c if (c as u32) < 0x20 => write!(buffer, "\\u{:04x}", c as u32).unwrap(),
+            c => {
+                let mut utf8 = [0; 4];
+                buffer.extend_from_slice(c.encode_utf8(&mut utf8).as_bytes());
+            }
+        }
+    }
+    buffer.push(b'"');
+}
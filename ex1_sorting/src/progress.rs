@@ -0,0 +1,116 @@
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Instant;
+
+use crate::memory::format_bytes;
+
+// The line is redrawn at most this often, however often work is reported.
+const REDRAW_MILLIS: u64 = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Unit {
+    Rows,
+    Bytes,
+}
+
+// A single-line progress report on stderr. Work can be reported from several
+// threads at once; a disabled Progress does nothing.
+pub struct Progress {
+    label: &'static str,
+    unit: Unit,
+    total: usize,
+    // Units of work `add` counts towards; `total` unless set_work changes it.
+    work: AtomicUsize,
+    done: AtomicUsize,
+    start: Instant,
+    last_draw: AtomicU64,
+    enabled: bool,
+}
+
+impl Progress {
+    // Only draws when stderr is a terminal, so redirected runs stay clean.
+    pub fn new(label: &'static str, unit: Unit, total: usize, enabled: bool) -> Self {
+        Progress {
+            label,
+            unit,
+            total,
+            work: AtomicUsize::new(total),
+            done: AtomicUsize::new(0),
+            start: Instant::now(),
+            last_draw: AtomicU64::new(0),
+            enabled: enabled && std::io::stderr().is_terminal(),
+        }
+    }
+
+    pub fn disabled() -> Self {
+        Progress::new("", Unit::Rows, 0, false)
+    }
+
+    // For algorithms whose work is not one unit per row, such as merge sort
+    // touching every row once per level.
+    pub fn set_work(&self, work: usize) {
+        self.work.store(work, Ordering::Relaxed);
+    }
+
+    pub fn add(&self, amount: usize) {
+        if !self.enabled {
+            return;
+        }
+        let done = self.done.fetch_add(amount, Ordering::Relaxed) + amount;
+
+        let now = self.start.elapsed().as_millis() as u64;
+        let last = self.last_draw.load(Ordering::Relaxed);
+        if now >= last + REDRAW_MILLIS
+            && self
+                .last_draw
+                .compare_exchange(last, now, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+        {
+            let work = self.work.load(Ordering::Relaxed).max(1);
+            let rows = (done.min(work) as u128 * self.total as u128 / work as u128) as usize;
+            self.draw(rows);
+        }
+    }
+
+    // Clears the line, so that the phase summary printed next starts clean.
+    pub fn finish(&self) {
+        if self.enabled && self.last_draw.load(Ordering::Relaxed) > 0 {
+            eprint!("\r\x1b[K");
+        }
+    }
+
+    fn draw(&self, done: usize) {
+        let elapsed = self.start.elapsed().as_secs_f64();
+        let rate = done as f64 / elapsed;
+        let eta = if done > 0 {
+            format!("{:.0}s", (self.total - done) as f64 / rate)
+        } else {
+            "?".to_string()
+        };
+        let (done_text, total_text, rate_text) = match self.unit {
+            Unit::Rows => (
+                done.to_string(),
+                self.total.to_string(),
+                format!("{:.0} rows/s", rate),
+            ),
+            Unit::Bytes => (
+                format_bytes(done),
+                format_bytes(self.total),
+                format!("{}/s", format_bytes(rate as usize)),
+            ),
+        };
+
+        let mut stderr = std::io::stderr().lock();
+        let _ = write!(
+            stderr,
+            "\r\x1b[K{}: {}/{} ({:.1}%), {}, ETA {}",
+            self.label,
+            done_text,
+            total_text,
+            done as f64 * 100.0 / self.total.max(1) as f64,
+            rate_text,
+            eta
+        );
+        let _ = stderr.flush();
+    }
+}
@@ -1,35 +1,121 @@
 use std::cmp::Ordering;
-use std::cmp::PartialOrd;
+use std::sync::Arc;
+
+use crate::deadline::{Cancelled, Deadline, CHECK_MIN_LEN};
+use crate::generate::Rng;
+use crate::heapsort::heap_sort;
+use crate::progress::Progress;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PivotStrategy {
+    First,
+    Middle,
+    Random,
+    MedianOfThree,
+    // Tukey's median of three medians of three, over nine samples.
+    Ninther,
+}
+
+impl PivotStrategy {
+    pub fn parse(name: &str) -> Option<PivotStrategy> {
+        match name.to_ascii_lowercase().as_str() {
+            "first" => Some(PivotStrategy::First),
+            "middle" => Some(PivotStrategy::Middle),
+            "random" => Some(PivotStrategy::Random),
+            "median3" | "median-of-3" | "median-of-three" => Some(PivotStrategy::MedianOfThree),
+            "ninther" => Some(PivotStrategy::Ninther),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct SortConfig {
+    // Ranges of at most this many elements are finished with insertion sort.
+    pub insertion_cutoff: usize,
+    pub pivot: PivotStrategy,
+    // Seed for the random pivot strategy.
+    pub seed: u64,
+    // Sorts check it periodically and stop early once it passes.
+    pub deadline: Option<Deadline>,
+    // Sorts that support it report the elements they have finished with.
+    pub progress: Option<Arc<Progress>>,
+}
+
+impl Default for SortConfig {
+    fn default() -> Self {
+        SortConfig {
+            insertion_cutoff: 16,
+            pivot: PivotStrategy::MedianOfThree,
+            seed: 42,
+            deadline: None,
+            progress: None,
+        }
+    }
+}
+
+impl SortConfig {
+    pub fn check_deadline(&self) -> Result<(), Cancelled> {
+        self.deadline.as_ref().map_or(Ok(()), Deadline::check)
+    }
+
+    pub fn report_progress(&self, amount: usize) {
+        if let Some(progress) = &self.progress {
+            progress.add(amount);
+        }
+    }
+}
 
 pub fn quick_sort<T, F>(arr: &mut [T], compar: &F)
 where
-    T: PartialOrd,
+    F: Fn(&T, &T) -> Ordering,
+{
+    // Without a deadline the sort always completes.
+    let _ = quick_sort_with_config(arr, compar, &SortConfig::default());
+}
+
+pub fn quick_sort_with_config<T, F>(
+    arr: &mut [T],
+    compar: &F,
+    config: &SortConfig,
+) -> Result<(), Cancelled>
+where
     F: Fn(&T, &T) -> Ordering,
 {
     if arr.len() <= 1 {
-        return;
+        return Ok(());
     }
-    quick_sort_recursive(arr, compar);
+    quick_sort_recursive(arr, compar, config)
 }
 
-fn quick_sort_recursive<T, F>(arr: &mut [T], compar: &F)
+fn quick_sort_recursive<T, F>(
+    arr: &mut [T],
+    compar: &F,
+    config: &SortConfig,
+) -> Result<(), Cancelled>
 where
     F: Fn(&T, &T) -> Ordering,
 {
+    let mut rng = Rng::new(config.seed);
     let mut stack = Vec::with_capacity(32);
     stack.push((0, arr.len()));
 
     while let Some((start, end)) = stack.pop() {
         let len = end - start;
 
-        if len <= 16 {
+        if len <= config.insertion_cutoff.max(1) {
             insertion_sort(&mut arr[start..end], compar);
+            config.report_progress(len);
             continue;
         }
+        if len >= CHECK_MIN_LEN {
+            config.check_deadline()?;
+        }
 
-        let pivot_idx = choose_pivot(&mut arr[start..end], compar) + start;
+        let pivot_idx = pick_pivot(&mut arr[start..end], compar, config.pivot, &mut rng) + start;
         arr.swap(pivot_idx, end - 1);
         let pivot_pos = partition(&mut arr[start..end], compar) + start;
+        config.report_progress(1);
 
         let left_len = pivot_pos - start;
         let right_len = end - (pivot_pos + 1);
@@ -42,6 +128,67 @@ where
             stack.push((start, pivot_pos));
         }
     }
+    Ok(())
+}
+
+pub fn select_nth<'a, T, F>(arr: &'a mut [T], k: usize, compar: &F) -> &'a T
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    assert!(
+        k < arr.len(),
+        "select_nth: index {} out of range for length {}",
+        k,
+        arr.len()
+    );
+
+    let mut start = 0;
+    let mut end = arr.len();
+    // Same bound as introsort: past ~2*log2(n) partitions the pivots are bad,
+    // so finish the remaining window with heapsort instead of going quadratic.
+    let mut budget = 2 * (usize::BITS - arr.len().leading_zeros()) as usize;
+
+    while end - start > 16 {
+        if budget == 0 {
+            heap_sort(&mut arr[start..end], compar);
+            return &arr[k];
+        }
+        budget -= 1;
+
+        let pivot_idx = choose_pivot(&mut arr[start..end], compar) + start;
+        arr.swap(pivot_idx, end - 1);
+        let pivot_pos = partition(&mut arr[start..end], compar) + start;
+
+        match k.cmp(&pivot_pos) {
+            Ordering::Equal => return &arr[k],
+            Ordering::Less => end = pivot_pos,
+            Ordering::Greater => start = pivot_pos + 1,
+        }
+    }
+
+    insertion_sort(&mut arr[start..end], compar);
+    &arr[k]
+}
+
+// Nearest-rank percentile, `p` in [0, 100].
+pub fn percentile<'a, T, F>(arr: &'a mut [T], p: f64, compar: &F) -> Option<&'a T>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    if arr.is_empty() || !(0.0..=100.0).contains(&p) {
+        return None;
+    }
+
+    let rank = (p / 100.0 * arr.len() as f64).ceil() as usize;
+    let k = rank.saturating_sub(1).min(arr.len() - 1);
+    Some(select_nth(arr, k, compar))
+}
+
+pub fn median<'a, T, F>(arr: &'a mut [T], compar: &F) -> Option<&'a T>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    percentile(arr, 50.0, compar)
 }
 
 fn choose_pivot<T, F>(arr: &mut [T], compar: &F) -> usize
@@ -68,6 +215,45 @@ where
     mid
 }
 
+fn pick_pivot<T, F>(arr: &mut [T], compar: &F, strategy: PivotStrategy, rng: &mut Rng) -> usize
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    match strategy {
+        PivotStrategy::First => 0,
+        PivotStrategy::Middle => arr.len() / 2,
+        PivotStrategy::Random => rng.below(arr.len()),
+        PivotStrategy::MedianOfThree => choose_pivot(arr, compar),
+        PivotStrategy::Ninther if arr.len() < 40 => choose_pivot(arr, compar),
+        PivotStrategy::Ninther => {
+            let step = arr.len() / 8;
+            let mid = arr.len() / 2;
+            let last = arr.len() - 1;
+            let a = median_of_three(arr, 0, step, 2 * step, compar);
+            let b = median_of_three(arr, mid - step, mid, mid + step, compar);
+            let c = median_of_three(arr, last - 2 * step, last - step, last, compar);
+            median_of_three(arr, a, b, c, compar)
+        }
+    }
+}
+
+// Index of the median of arr[a], arr[b], arr[c], without moving elements.
+fn median_of_three<T, F>(arr: &[T], a: usize, b: usize, c: usize, compar: &F) -> usize
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    let ab = compar(&arr[a], &arr[b]) == Ordering::Less;
+    let bc = compar(&arr[b], &arr[c]) == Ordering::Less;
+    let ac = compar(&arr[a], &arr[c]) == Ordering::Less;
+    if ab == bc {
+        b
+    } else if ab == ac {
+        c
+    } else {
+        a
+    }
+}
+
 fn partition<T, F>(arr: &mut [T], compar: &F) -> usize
 where
     F: Fn(&T, &T) -> Ordering,
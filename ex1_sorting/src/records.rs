@@ -1,16 +1,31 @@
-use inquire::Select;
+use inquire::{Confirm, Select};
 use memmap2::Mmap;
 use rayon::prelude::*;
 use smallstr::SmallString;
 use std::cmp::Ordering;
-use std::error::Error;
-use std::fs::{File, OpenOptions};
-use std::io::Write;
-use std::time::Instant;
-
-use crate::compar::{compare_f64_quicksort, compare_str_lex};
-use crate::mergesort::merge_sort;
-use crate::quicksort::quick_sort;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::ops::Deref;
+use std::path::Path;
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+
+use crate::cache::{load_cache, write_cache};
+use crate::cachedkey::sort_by_cached_key;
+use crate::check::verify;
+use crate::compar::{
+    compare_f64_quicksort, compare_str_lex, compare_str_natural, compare_str_nocase, fold_case,
+    natural_key, Collation,
+};
+use crate::deadline::Deadline;
+use crate::error::SortError;
+use crate::expr::Expr;
+use crate::group::{dedup_sorted, group_sorted, write_groups, Keep};
+use crate::memory::PhaseMemory;
+use crate::output::{render_record, OutputFormat, OutputSpec};
+use crate::progress::{Progress, Unit};
+use crate::quicksort::{median, percentile, SortConfig};
+use crate::sorter::find_sorter;
 
 #[cfg(target_arch = "x86_64")]
 use std::arch::x86_64::*;
@@ -18,78 +33,465 @@ use std::arch::x86_64::*;
 type SmallStr = SmallString<[u8; 32]>;
 
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
-struct Record {
-    name: SmallStr,
-    value1: i64,
-    value2: f64,
-    line_range: (usize, usize),
+pub struct Record {
+    pub id: i64,
+    pub name: SmallStr,
+    pub value1: i64,
+    pub value2: f64,
+    pub line_range: (usize, usize),
+}
+
+pub struct SortOptions<'a> {
+    pub input: &'a str,
+    pub output: &'a str,
+    pub algorithm: &'a str,
+    pub column: &'a str,
+    pub verify: bool,
+    pub output_spec: OutputSpec,
+    pub config: SortConfig,
+    // Field delimiter of the input; detected from the first lines if None.
+    pub delimiter: Option<u8>,
+    // Number of chunks parsed in parallel; defaults to one per rayon thread.
+    pub parse_chunks: Option<usize>,
+    // Keep a single record per distinct sort key. Needs a stable sort, so
+    // that first and last refer to the input order.
+    pub unique: Option<Keep>,
+    // Write one summary line per distinct sort key instead of the records.
+    pub group_count: bool,
+    // How names compare; other collations than Binary need the Name column.
+    pub collation: Collation,
+    // Sorting stops once it has run this long; the run then fails with
+    // SortError::TimedOut and writes no output.
+    pub time_limit: Option<Duration>,
+    // Parsed records are loaded from and saved to this file when given.
+    pub cache: Option<&'a str>,
+    // Show progress of each phase on stderr, when it is a terminal.
+    pub progress: bool,
+    // Computed sort key, used instead of `column` when given.
+    pub key: Option<Expr>,
+    // Only rows matching this condition are kept, checked while parsing.
+    pub filter: Option<Expr>,
+    // Parse as parse_record_lenient does instead of failing on bad rows.
+    pub lenient: bool,
+}
+
+// Input bytes are mmapped for regular files; `-` reads standard input into
+// memory, since a pipe cannot be mapped.
+pub enum Input {
+    Mapped(Mmap),
+    Buffered(Vec<u8>),
+}
+
+impl Input {
+    pub fn open(path: &str) -> Result<Input, SortError> {
+        if path == "-" {
+            let mut buffer = Vec::new();
+            std::io::stdin()
+                .lock()
+                .read_to_end(&mut buffer)
+                .map_err(SortError::io(path))?;
+            Ok(Input::Buffered(buffer))
+        } else {
+            let file = File::open(path).map_err(SortError::io(path))?;
+            let mmap = unsafe { Mmap::map(&file).map_err(SortError::io(path))? };
+            Ok(Input::Mapped(mmap))
+        }
+    }
+}
+
+impl Deref for Input {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Input::Mapped(mmap) => mmap,
+            Input::Buffered(buffer) => buffer,
+        }
+    }
 }
 
-pub fn run_sorting_with_records(algorithm_choice: &str) -> Result<(), Box<dyn Error>> {
+pub fn run_sorting_with_records(algorithm_choice: &str) -> Result<(), SortError> {
     let sort_column_choice = Select::new(
         "Choose a column to sort by",
         vec!["Name", "Value1", "Value2"],
     )
     .prompt()?;
 
+    let verify_choice = Confirm::new("Verify the sorted output?")
+        .with_default(false)
+        .prompt()?;
+
+    run_sort(&SortOptions {
+        input: "rsrc/records.csv",
+        output: "tmp/sorted_output.csv",
+        algorithm: algorithm_choice,
+        column: sort_column_choice,
+        verify: verify_choice,
+        output_spec: OutputSpec::default(),
+        config: SortConfig::default(),
+        delimiter: None,
+        parse_chunks: None,
+        unique: None,
+        group_count: false,
+        collation: Collation::Binary,
+        time_limit: None,
+        cache: None,
+        progress: true,
+        key: None,
+        filter: None,
+        lenient: false,
+    })
+}
+
+pub fn run_sort(options: &SortOptions) -> Result<(), SortError> {
+    if is_same_file(options.input, options.output) {
+        return Err(SortError::OutputIsInput(options.output.to_string()));
+    }
+    // Keys and collations sort by cached key, which is stable whatever the
+    // algorithm; otherwise the algorithm has to be.
+    let sorter = find_sorter::<Record, fn(&Record, &Record) -> Ordering>(options.algorithm)
+        .ok_or_else(|| SortError::InvalidAlgorithm(options.algorithm.to_string()))?;
+    if options.unique.is_some()
+        && options.key.is_none()
+        && options.collation == Collation::Binary
+        && !sorter.is_stable()
+    {
+        return Err(SortError::Usage(format!(
+            "--unique needs a stable algorithm, and {} is not stable",
+            sorter.name()
+        )));
+    }
+    let column_cmp = collated_comparator(options.column, options.collation)
+        .ok_or_else(|| SortError::InvalidColumn(options.column.to_string()))?;
+    let cmp = |a: &Record, b: &Record| match &options.key {
+        Some(key) => key.compare(a, b),
+        None => column_cmp(a, b),
+    };
+    let format_key = |record: &Record| match &options.key {
+        Some(key) => key.eval(record).to_string(),
+        None => format_column(record, options.column),
+    };
+
+    // Keep stdout clean for the data when the output is piped.
+    let to_stdout = options.output == "-";
+    let log = |message: String| {
+        if to_stdout {
+            eprintln!("{}", message);
+        } else {
+            println!("{}", message);
+        }
+    };
+
     let start_total = Instant::now();
 
-    let (mmap, mut records) = {
+    let (input, delimiter, mut records) = {
         let start_reading = Instant::now();
-        let file = File::open("rsrc/records.csv")?;
-        let mmap = unsafe { Mmap::map(&file)? };
-        let records = parse_csv(&mmap)?;
-        println!("Reading and parsing time: {:.4?}", start_reading.elapsed());
-        (mmap, records)
+        let memory = PhaseMemory::start();
+        let input = Input::open(options.input)?;
+        // Standard input has no size or mtime to validate a cache against.
+        let cache = options.cache.filter(|_| options.input != "-");
+        let cached = match cache {
+            Some(cache_path) => load_cache(cache_path, options.input, &input, options.delimiter)?,
+            None => None,
+        };
+        let (delimiter, records) = match cached {
+            Some((delimiter, mut records)) => {
+                log(format!("Loaded {} records from cache", records.len()));
+                if let Some(filter) = &options.filter {
+                    records.retain(|record| filter.matches(record));
+                }
+                (delimiter, records)
+            }
+            None => {
+                let delimiter = options
+                    .delimiter
+                    .unwrap_or_else(|| detect_delimiter(&input));
+                let progress = Progress::new("Parsing", Unit::Bytes, input.len(), options.progress);
+                let records = parse_csv(
+                    &input,
+                    options.input,
+                    delimiter,
+                    options.parse_chunks,
+                    options.filter.as_ref(),
+                    options.lenient,
+                    &progress,
+                )?;
+                progress.finish();
+                // The cache holds every row, so a filtered parse cannot fill it,
+                // and one a lenient parse skipped rows of would hide them from
+                // later strict runs.
+                if let Some(cache_path) =
+                    cache.filter(|_| options.filter.is_none() && !options.lenient)
+                {
+                    // A cache that cannot be written only costs the next run a parse.
+                    match write_cache(cache_path, options.input, &input, delimiter, &records) {
+                        Ok(()) => log(format!("Wrote cache {}", cache_path)),
+                        Err(e) => log(format!("Cache not written: {}", e)),
+                    }
+                }
+                (delimiter, records)
+            }
+        };
+        if options.filter.is_some() {
+            log(format!("{} records match the filter", records.len()));
+        }
+        log(format!(
+            "Reading and parsing time: {:.4?}",
+            start_reading.elapsed()
+        ));
+        log(format!(
+            "Reading and parsing memory: {}",
+            memory.finish().summary()
+        ));
+        (input, delimiter, records)
     };
 
     let sort_start = Instant::now();
-    match sort_column_choice {
-        "Name" => sort_records(&mut records, algorithm_choice, |a, b| {
-            compare_str_lex(&a.name, &b.name)
-        }),
-        "Value1" => sort_records(&mut records, algorithm_choice, |a, b| {
-            a.value1.partial_cmp(&b.value1).unwrap_or(Ordering::Equal)
-        }),
-        "Value2" => sort_records(&mut records, algorithm_choice, |a, b| {
-            compare_f64_quicksort(a.value2, b.value2)
-        }),
-        _ => eprintln!("Invalid column selected."),
-    }
-    println!("Sorting time: {:.4?}", sort_start.elapsed());
+    let memory = PhaseMemory::start();
+    let progress = Arc::new(Progress::new(
+        "Sorting",
+        Unit::Rows,
+        records.len(),
+        options.progress,
+    ));
+    let config = SortConfig {
+        deadline: options.time_limit.map(Deadline::after),
+        progress: Some(progress.clone()),
+        ..options.config.clone()
+    };
+    // Computed keys and collated names are evaluated once per record instead
+    // of once per comparison.
+    let sorted = if let Some(key) = &options.key {
+        sort_records_by_key(
+            &mut records,
+            options.algorithm,
+            |record| key.eval(record),
+            &config,
+        )
+    } else {
+        match options.collation {
+            Collation::Binary => sort_records(&mut records, options.algorithm, cmp, &config),
+            Collation::CaseInsensitive => sort_records_by_key(
+                &mut records,
+                options.algorithm,
+                |record| fold_case(&record.name),
+                &config,
+            ),
+            Collation::Natural => sort_records_by_key(
+                &mut records,
+                options.algorithm,
+                |record| natural_key(&record.name),
+                &config,
+            ),
+        }
+    };
+    progress.finish();
+    sorted?;
+    log(format!("Sorting time: {:.4?}", sort_start.elapsed()));
+    log(format!("Sorting memory: {}", memory.finish().summary()));
+
+    if let Some(keep) = options.unique {
+        let before = records.len();
+        dedup_sorted(&mut records, cmp, keep);
+        log(format!(
+            "Removed {} records with duplicate keys",
+            before - records.len()
+        ));
+    }
 
     let write_start = Instant::now();
-    write_sorted_csv("tmp/sorted_output.csv", &records, &mmap)?;
-    println!("Writing time: {:.4?}", write_start.elapsed());
+    let memory = PhaseMemory::start();
+    let mut output_spec = options.output_spec.clone();
+    output_spec.delimiter.get_or_insert(delimiter);
+    if options.group_count {
+        let groups = group_sorted(&records, cmp, format_key);
+        log(format!("Distinct keys: {}", groups.len()));
+        write_output(options.output, |writer| {
+            write_groups(writer, &groups, &output_spec)
+        })?;
+    } else {
+        let progress = Progress::new("Writing", Unit::Rows, records.len(), options.progress);
+        write_sorted_csv(options.output, &records, &input, &output_spec, &progress)?;
+        progress.finish();
+    }
+    log(format!("Writing time: {:.4?}", write_start.elapsed()));
+    log(format!("Writing memory: {}", memory.finish().summary()));
+
+    log(format!(
+        "Total execution time: {:.4?}",
+        start_total.elapsed()
+    ));
+
+    let raw_output = options.output_spec.format == OutputFormat::Raw
+        && options.unique.is_none()
+        && !options.group_count;
+    if options.verify && !raw_output {
+        log("Verification skipped: output is not the input records reordered".to_string());
+    }
+    if options.verify && raw_output && !to_stdout {
+        let verify_start = Instant::now();
+        let output = File::open(options.output).map_err(SortError::io(options.output))?;
+        let verification = verify(
+            (BufReader::new(output), options.output),
+            // Filtered output is a subset of the input, so only its order is checked.
+            Some((&input[..], options.input)).filter(|_| options.filter.is_none()),
+            &cmp,
+            delimiter,
+        )?;
+        log(format!("Verification: {}", verification));
+        log(format!("Verification time: {:.4?}", verify_start.elapsed()));
+        if !verification.is_ok() {
+            return Err(SortError::VerificationFailed(options.output.to_string()));
+        }
+    }
+    Ok(())
+}
+
+pub fn run_statistics_with_records() -> Result<(), SortError> {
+    let column_choice = Select::new(
+        "Choose a column to summarize",
+        vec!["Name", "Value1", "Value2"],
+    )
+    .prompt()?;
+
+    let cmp = column_comparator(column_choice)
+        .ok_or_else(|| SortError::InvalidColumn(column_choice.to_string()))?;
+
+    let input = Input::open("rsrc/records.csv")?;
+    let mut records = parse_csv(
+        &input,
+        "rsrc/records.csv",
+        detect_delimiter(&input),
+        None,
+        None,
+        false,
+        &Progress::disabled(),
+    )?;
+
+    let select_start = Instant::now();
+    println!("Records: {}", records.len());
+    if let Some(min) = percentile(&mut records, 0.0, &cmp) {
+        println!("Min: {}", format_column(min, column_choice));
+    }
+    if let Some(med) = median(&mut records, &cmp) {
+        println!("Median: {}", format_column(med, column_choice));
+    }
+    for p in [90.0, 99.0] {
+        if let Some(record) = percentile(&mut records, p, &cmp) {
+            println!("p{}: {}", p, format_column(record, column_choice));
+        }
+    }
+    if let Some(max) = percentile(&mut records, 100.0, &cmp) {
+        println!("Max: {}", format_column(max, column_choice));
+    }
+    println!("Selection time: {:.4?}", select_start.elapsed());
 
-    println!("Total execution time: {:.4?}", start_total.elapsed());
     Ok(())
 }
 
+pub fn column_comparator(column: &str) -> Option<fn(&Record, &Record) -> Ordering> {
+    match column {
+        "Name" => Some(|a, b| compare_str_lex(&a.name, &b.name)),
+        "Value1" => Some(|a, b| a.value1.partial_cmp(&b.value1).unwrap_or(Ordering::Equal)),
+        "Value2" => Some(|a, b| compare_f64_quicksort(a.value2, b.value2)),
+        _ => None,
+    }
+}
+
+// Like column_comparator, with names compared under `collation`. None if the
+// collation does not apply to the column.
+pub fn collated_comparator(
+    column: &str,
+    collation: Collation,
+) -> Option<fn(&Record, &Record) -> Ordering> {
+    match (column, collation) {
+        (_, Collation::Binary) => column_comparator(column),
+        ("Name", Collation::CaseInsensitive) => Some(|a, b| compare_str_nocase(&a.name, &b.name)),
+        ("Name", Collation::Natural) => Some(|a, b| compare_str_natural(&a.name, &b.name)),
+        _ => None,
+    }
+}
+
+pub fn format_column(record: &Record, column: &str) -> String {
+    match column {
+        "Name" => record.name.to_string(),
+        "Value1" => record.value1.to_string(),
+        _ => record.value2.to_string(),
+    }
+}
+
+const DELIMITER_CANDIDATES: [u8; 4] = [b',', b'\t', b';', b'|'];
+
+// Picks the candidate that splits the first lines into exactly 4 fields, and
+// failing that the one that occurs at least 3 times with the same count on
+// every sampled line. Defaults to a comma.
+pub fn detect_delimiter(bytes: &[u8]) -> u8 {
+    let sample: Vec<&[u8]> = bytes
+        .split(|&b| b == b'\n')
+        .filter(|line| !line.is_empty())
+        .take(10)
+        .collect();
+    if sample.is_empty() {
+        return b',';
+    }
+
+    let counts = |delimiter: u8| -> Option<usize> {
+        let first = memchr::memchr_iter(delimiter, sample[0]).count();
+        sample[1..]
+            .iter()
+            .all(|line| memchr::memchr_iter(delimiter, line).count() == first)
+            .then_some(first)
+    };
+
+    DELIMITER_CANDIDATES
+        .into_iter()
+        .find(|&d| counts(d) == Some(3))
+        .or_else(|| {
+            DELIMITER_CANDIDATES
+                .into_iter()
+                .find(|&d| counts(d).is_some_and(|n| n >= 3))
+        })
+        .unwrap_or(b',')
+}
+
+pub fn parse_delimiter(name: &str) -> Option<u8> {
+    match name.to_ascii_lowercase().as_str() {
+        "tab" | "\\t" | "\t" => Some(b'\t'),
+        "comma" => Some(b','),
+        "semicolon" => Some(b';'),
+        "pipe" => Some(b'|'),
+        _ if name.len() == 1 && name.as_bytes()[0] != b'\n' => Some(name.as_bytes()[0]),
+        _ => None,
+    }
+}
+
 #[cfg(target_arch = "x86_64")]
-unsafe fn find_commas_simd(bytes: &[u8]) -> (Option<usize>, Option<usize>, Option<usize>) {
-    let mut comma1 = None;
-    let mut comma2 = None;
-    let mut comma3 = None;
-    let comma = _mm_set1_epi8(b',' as i8);
+unsafe fn find_delimiters_simd(
+    bytes: &[u8],
+    delimiter: u8,
+) -> (Option<usize>, Option<usize>, Option<usize>) {
+    let mut delim1 = None;
+    let mut delim2 = None;
+    let mut delim3 = None;
+    let needle = _mm_set1_epi8(delimiter as i8);
     let mut i = 0;
 
     while i + 16 <= bytes.len() {
         let chunk = _mm_loadu_si128(bytes.as_ptr().add(i) as *const __m128i);
-        let eq = _mm_cmpeq_epi8(chunk, comma);
+        let eq = _mm_cmpeq_epi8(chunk, needle);
         let mask = _mm_movemask_epi8(eq) as u32;
 
         if mask != 0 {
             for j in 0..16 {
                 if mask & (1 << j) != 0 {
                     let pos = i + j;
-                    if comma1.is_none() {
-                        comma1 = Some(pos);
-                    } else if comma2.is_none() {
-                        comma2 = Some(pos);
-                    } else if comma3.is_none() {
-                        comma3 = Some(pos);
-                        return (comma1, comma2, comma3);
+                    if delim1.is_none() {
+                        delim1 = Some(pos);
+                    } else if delim2.is_none() {
+                        delim2 = Some(pos);
+                    } else if delim3.is_none() {
+                        delim3 = Some(pos);
+                        return (delim1, delim2, delim3);
                     }
                 }
             }
@@ -98,26 +500,33 @@ unsafe fn find_commas_simd(bytes: &[u8]) -> (Option<usize>, Option<usize>, Optio
     }
 
     for (j, &b) in bytes[i..].iter().enumerate() {
-        if b == b',' {
+        if b == delimiter {
             let pos = i + j;
-            if comma1.is_none() {
-                comma1 = Some(pos);
-            } else if comma2.is_none() {
-                comma2 = Some(pos);
-            } else if comma3.is_none() {
-                comma3 = Some(pos);
+            if delim1.is_none() {
+                delim1 = Some(pos);
+            } else if delim2.is_none() {
+                delim2 = Some(pos);
+            } else if delim3.is_none() {
+                delim3 = Some(pos);
                 break;
             }
         }
     }
 
-    (comma1, comma2, comma3)
+    (delim1, delim2, delim3)
 }
 
-fn parse_csv(mmap: &Mmap) -> Result<Vec<Record>, Box<dyn Error>> {
-    let bytes = mmap.as_ref();
-    let num_chunks = rayon::current_num_threads();
-    let chunk_size = (bytes.len() + num_chunks - 1) / num_chunks;
+pub fn parse_csv(
+    bytes: &[u8],
+    path: &str,
+    delimiter: u8,
+    chunks: Option<usize>,
+    filter: Option<&Expr>,
+    lenient: bool,
+    progress: &Progress,
+) -> Result<Vec<Record>, SortError> {
+    let num_chunks = chunks.unwrap_or_else(rayon::current_num_threads).max(1);
+    let chunk_size = bytes.len().div_ceil(num_chunks);
 
     let chunk_boundaries: Vec<usize> = (0..=num_chunks)
         .map(|i| {
@@ -138,105 +547,289 @@ fn parse_csv(mmap: &Mmap) -> Result<Vec<Record>, Box<dyn Error>> {
         })
         .collect();
 
-    let records: Vec<Record> = chunk_boundaries
+    let chunks: Vec<Vec<Record>> = chunk_boundaries
         .par_windows(2)
-        .flat_map(|window| {
+        .map(|window| {
             let chunk_start = window[0];
             let chunk_end = window[1];
             let mut records = Vec::with_capacity(1024);
             let mut pos = chunk_start;
+            let mut reported = chunk_start;
 
             while pos < chunk_end {
                 let line_start = pos;
                 while pos < chunk_end && bytes[pos] != b'\n' {
                     pos += 1;
                 }
-
-                if pos > line_start {
-                    let line_end = pos;
-                    pos += 1;
-
-                    let (comma1, comma2, comma3) =
-                        unsafe { find_commas_simd(&bytes[line_start..line_end]) };
-
-                    if let (Some(c1), Some(c2), Some(c3)) = (comma1, comma2, comma3) {
-                        let name_bytes = &bytes[line_start + c1 + 1..line_start + c2];
-                        let name = match std::str::from_utf8(name_bytes) {
-                            Ok(s) => SmallStr::from(s),
-                            Err(_) => SmallStr::from(String::from_utf8_lossy(name_bytes).as_ref()),
-                        };
-
-                        let value1 = unsafe {
-                            std::str::from_utf8_unchecked(
-                                &bytes[line_start + c2 + 1..line_start + c3],
-                            )
-                            .parse()
-                            .unwrap_or(0)
-                        };
-
-                        let value2 = unsafe {
-                            std::str::from_utf8_unchecked(&bytes[line_start + c3 + 1..line_end])
-                                .parse()
-                                .unwrap_or(f64::NAN)
-                        };
-
-                        records.push(Record {
-                            name,
-                            value1,
-                            value2,
-                            line_range: (line_start, line_end),
-                        });
+                let line_end = pos;
+                pos += 1;
+
+                if line_end > line_start {
+                    let line = &bytes[line_start..line_end];
+                    let record = if lenient {
+                        parse_record_lenient(line, line_start, delimiter)
+                    } else {
+                        Some(
+                            parse_record(line, line_start, delimiter)
+                                .map_err(|message| (line_start, message))?,
+                        )
+                    };
+                    if let Some(record) =
+                        record.filter(|record| filter.is_none_or(|filter| filter.matches(record)))
+                    {
+                        records.push(record);
                     }
                 }
+                if pos - reported >= 1 << 20 {
+                    progress.add(pos - reported);
+                    reported = pos;
+                }
             }
+            progress.add(chunk_end.saturating_sub(reported));
 
-            records
+            Ok(records)
         })
-        .collect();
+        .collect::<Result<_, (usize, String)>>()
+        .map_err(|(offset, message)| SortError::Parse {
+            path: path.to_string(),
+            line: memchr::memchr_iter(b'\n', &bytes[..offset]).count() + 1,
+            message,
+        })?;
+
+    let mut records = Vec::with_capacity(chunks.iter().map(Vec::len).sum());
+    for chunk in chunks {
+        records.extend(chunk);
+    }
 
     Ok(records)
 }
 
-fn sort_records<F>(records: &mut [Record], algorithm: &str, cmp: F)
+pub fn parse_record(line: &[u8], offset: usize, delimiter: u8) -> Result<Record, String> {
+    let (delim1, delim2, delim3) = unsafe { find_delimiters_simd(line, delimiter) };
+    let (Some(c1), Some(c2), Some(c3)) = (delim1, delim2, delim3) else {
+        return Err(format!(
+            "expected 4 fields separated by '{}'",
+            (delimiter as char).escape_default()
+        ));
+    };
+
+    Ok(Record {
+        id: parse_field(&line[..c1], "Id")?,
+        name: parse_name(&line[c1 + 1..c2]),
+        value1: parse_field(&line[c2 + 1..c3], "Value1")?,
+        value2: parse_field(&line[c3 + 1..], "Value2")?,
+        line_range: (offset, offset + line.len()),
+    })
+}
+
+// How rows were read before parse errors were reported: lines with fewer
+// than four fields are skipped, and numbers that do not parse become 0, or
+// NaN for Value2.
+pub fn parse_record_lenient(line: &[u8], offset: usize, delimiter: u8) -> Option<Record> {
+    let (delim1, delim2, delim3) = unsafe { find_delimiters_simd(line, delimiter) };
+    let (c1, c2, c3) = (delim1?, delim2?, delim3?);
+
+    Some(Record {
+        id: parse_field(&line[..c1], "Id").unwrap_or(0),
+        name: parse_name(&line[c1 + 1..c2]),
+        value1: parse_field(&line[c2 + 1..c3], "Value1").unwrap_or(0),
+        value2: parse_field(&line[c3 + 1..], "Value2").unwrap_or(f64::NAN),
+        line_range: (offset, offset + line.len()),
+    })
+}
+
+fn parse_name(bytes: &[u8]) -> SmallStr {
+    match std::str::from_utf8(bytes) {
+        Ok(s) => SmallStr::from(s),
+        Err(_) => SmallStr::from(String::from_utf8_lossy(bytes).as_ref()),
+    }
+}
+
+fn parse_field<N: std::str::FromStr>(bytes: &[u8], column: &str) -> Result<N, String> {
+    std::str::from_utf8(bytes)
+        .ok()
+        .and_then(|text| text.parse().ok())
+        .ok_or_else(|| format!("invalid {} '{}'", column, String::from_utf8_lossy(bytes)))
+}
+
+fn sort_records<F>(
+    records: &mut [Record],
+    algorithm: &str,
+    cmp: F,
+    config: &SortConfig,
+) -> Result<(), SortError>
 where
-    F: Fn(&Record, &Record) -> Ordering + Sync,
+    F: Fn(&Record, &Record) -> Ordering,
 {
-    match algorithm {
-        "Merge Sort" => merge_sort(records, &cmp),
-        "Quick Sort" => quick_sort(records, &cmp),
-        _ => eprintln!("Invalid algorithm selected."),
-    }
+    let sorter =
+        find_sorter(algorithm).ok_or_else(|| SortError::InvalidAlgorithm(algorithm.to_string()))?;
+    sorter
+        .sort_with_config(records, &cmp, config)
+        .map_err(SortError::TimedOut)
+}
+
+fn sort_records_by_key<K, KF>(
+    records: &mut [Record],
+    algorithm: &str,
+    key: KF,
+    config: &SortConfig,
+) -> Result<(), SortError>
+where
+    K: Ord + Clone,
+    KF: Fn(&Record) -> K,
+{
+    let sorter = find_sorter::<(K, usize), fn(&(K, usize), &(K, usize)) -> Ordering>(algorithm)
+        .ok_or_else(|| SortError::InvalidAlgorithm(algorithm.to_string()))?;
+    // The original index breaks ties, so the result is stable whatever the
+    // algorithm.
+    sort_by_cached_key(records, key, |keyed| {
+        let compar: fn(&(K, usize), &(K, usize)) -> Ordering = |a, b| a.cmp(b);
+        sorter.sort_with_config(keyed, &compar, config)
+    })
+    .map_err(SortError::TimedOut)
 }
 
 fn write_sorted_csv(
     output_path: &str,
     records: &[Record],
-    mmap: &Mmap,
-) -> Result<(), Box<dyn Error>> {
+    bytes: &[u8],
+    spec: &OutputSpec,
+    progress: &Progress,
+) -> Result<(), SortError> {
+    write_output(output_path, |writer| {
+        write_records(writer, records, bytes, spec, progress)
+    })
+}
+
+// `-` writes to standard output, anything else goes through write_atomically.
+pub fn write_output<F>(output_path: &str, write: F) -> Result<(), SortError>
+where
+    F: FnOnce(&mut dyn Write) -> std::io::Result<()>,
+{
+    if output_path == "-" {
+        let stdout = std::io::stdout().lock();
+        let mut writer = BufWriter::with_capacity(1024 * 1024 * 32, stdout);
+        return write(&mut writer).map_err(SortError::io(output_path));
+    }
+
+    write_atomically(output_path, |writer| write(writer))
+}
+
+// Writes to a temporary file next to `output_path` and renames it into place
+// once complete, so a crash never leaves a half-written output behind.
+pub fn write_atomically<F>(output_path: &str, write: F) -> Result<(), SortError>
+where
+    F: FnOnce(&mut BufWriter<File>) -> std::io::Result<()>,
+{
+    let path = Path::new(output_path);
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| SortError::Usage(format!("'{}' is not a file path", output_path)))?;
+    let temp_path = path.with_file_name(format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+
+    // Left alone on failure: if it already exists, it belongs to someone else.
     let file = OpenOptions::new()
         .write(true)
-        .truncate(true)
-        .create(true)
-        .open(output_path)?;
-    let mut writer = std::io::BufWriter::with_capacity(1024 * 1024 * 32, file);
-
-    let chunks: Vec<_> = records
-        .par_chunks(1000)
-        .map(|chunk| {
-            let mut buffer = Vec::with_capacity(1024 * 128);
-            for record in chunk {
-                buffer.extend_from_slice(&mmap[record.line_range.0..=record.line_range.1]);
-                if !buffer.ends_with(b"\n") {
-                    buffer.push(b'\n');
-                }
-            }
-            buffer
-        })
-        .collect();
+        .create_new(true)
+        .open(&temp_path)
+        .map_err(SortError::io(&temp_path.to_string_lossy()))?;
+
+    let result = (|| {
+        // Replacing an existing output keeps its permissions.
+        if let Ok(metadata) = fs::metadata(path) {
+            file.set_permissions(metadata.permissions())?;
+        }
+        let mut writer = BufWriter::with_capacity(1024 * 1024 * 32, file);
+        write(&mut writer)?;
+        let file = writer.into_inner().map_err(|e| e.into_error())?;
+        file.sync_all()?;
+        fs::rename(&temp_path, path)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result.map_err(SortError::io(output_path))
+}
 
-    for chunk in chunks {
-        writer.write_all(&chunk)?;
+// Compares device and inode where available, so hard links and different
+// spellings of the same path are caught, then falls back to canonical paths.
+pub fn is_same_file(a: &str, b: &str) -> bool {
+    if a == "-" || b == "-" {
+        return false;
     }
 
-    Ok(())
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        if let (Ok(ma), Ok(mb)) = (fs::metadata(a), fs::metadata(b)) {
+            return ma.dev() == mb.dev() && ma.ino() == mb.ino();
+        }
+    }
+
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+const CHUNK_RECORDS: usize = 1000;
+
+// Chunks are rendered in parallel on a separate thread, one window of
+// buffers at a time, while this thread writes the previous window in order.
+// At most three windows exist at once (being rendered, queued, being
+// written) and their buffers are reused, so memory does not grow with the
+// size of the output.
+fn write_records<W: Write>(
+    mut writer: W,
+    records: &[Record],
+    bytes: &[u8],
+    spec: &OutputSpec,
+    progress: &Progress,
+) -> std::io::Result<()> {
+    let window = rayon::current_num_threads() * 2;
+    let (filled_tx, filled_rx) = mpsc::sync_channel::<Vec<Vec<u8>>>(1);
+    let (empty_tx, empty_rx) = mpsc::channel::<Vec<u8>>();
+
+    std::thread::scope(|scope| {
+        scope.spawn(move || {
+            for window_records in records.chunks(CHUNK_RECORDS * window) {
+                let mut buffers: Vec<Vec<u8>> = window_records
+                    .chunks(CHUNK_RECORDS)
+                    .map(|_| {
+                        empty_rx
+                            .try_recv()
+                            .unwrap_or_else(|_| Vec::with_capacity(1024 * 128))
+                    })
+                    .collect();
+                buffers
+                    .par_iter_mut()
+                    .zip(window_records.par_chunks(CHUNK_RECORDS))
+                    .for_each(|(buffer, chunk)| {
+                        buffer.clear();
+                        for record in chunk {
+                            render_record(buffer, record, bytes, spec);
+                        }
+                    });
+                // The writer hung up after an error.
+                if filled_tx.send(buffers).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut chunk_lens = records.chunks(CHUNK_RECORDS).map(<[Record]>::len);
+        for buffers in filled_rx {
+            for buffer in buffers {
+                writer.write_all(&buffer)?;
+                progress.add(chunk_lens.next().unwrap_or(0));
+                let _ = empty_tx.send(buffer);
+            }
+        }
+        writer.flush()
+    })
 }
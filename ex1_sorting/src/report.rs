@@ -0,0 +1,270 @@
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::fmt::Write as _;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+use crate::deadline::{Cancelled, Deadline};
+use crate::error::SortError;
+use crate::generate::{generate_i64, generate_records};
+use crate::progress::Progress;
+use crate::quicksort::SortConfig;
+use crate::records::{
+    column_comparator, detect_delimiter, parse_csv, write_atomically, Input, Record,
+};
+use crate::sorter::find_sorter;
+
+const FIELDS: [&str; 3] = ["Name", "Value1", "Value2"];
+const BASELINE: &str = "slice::sort_by";
+
+pub struct ReportOptions<'a> {
+    pub output: &'a str,
+    // Records file to measure on, as loaded by the sort command.
+    pub input: Option<&'a str>,
+    // Generated inputs, each arranged by the field being sorted on.
+    pub distributions: Vec<&'static str>,
+    pub size: usize,
+    pub disorder: f64,
+    pub seed: u64,
+    pub algorithms: Vec<&'static str>,
+    pub config: SortConfig,
+    pub time_limit: Duration,
+}
+
+struct Measurement {
+    elapsed: Result<Duration, Cancelled>,
+    comparisons: u64,
+    sorted: bool,
+}
+
+// One table per dataset: a row per field, a column per algorithm plus the
+// baseline.
+struct Dataset {
+    title: String,
+    size: usize,
+    rows: Vec<(&'static str, Vec<Measurement>)>,
+}
+
+pub fn run_report(options: &ReportOptions) -> Result<(), SortError> {
+    let mut datasets = Vec::new();
+
+    if let Some(path) = options.input {
+        let input = Input::open(path)?;
+        let records = parse_csv(
+            &input,
+            path,
+            detect_delimiter(&input),
+            None,
+            None,
+            false,
+            &Progress::disabled(),
+        )?;
+        datasets.push(measure_dataset(format!("`{}`", path), options, |_| {
+            records.clone()
+        }));
+    }
+
+    if !options.distributions.is_empty() {
+        let records = generate_records(options.size, options.seed);
+        for &distribution in &options.distributions {
+            datasets.push(measure_dataset(
+                format!("{} (generated)", distribution),
+                options,
+                |field| arrange(&records, field, distribution, options),
+            ));
+        }
+    }
+
+    let markdown = render_markdown(&datasets, options);
+    write_atomically(options.output, |writer| {
+        writer.write_all(markdown.as_bytes())
+    })?;
+    println!("Report written to {}", options.output);
+    Ok(())
+}
+
+// Orders the records by `field` as the distribution prescribes. Sorted,
+// nearly sorted and reversed are permutations of ranks from generate_i64.
+fn arrange(
+    records: &[Record],
+    field: &str,
+    distribution: &str,
+    options: &ReportOptions,
+) -> Vec<Record> {
+    if distribution == "Random" {
+        return records.to_vec();
+    }
+    let cmp = column_comparator(field).unwrap();
+    let mut ranked = records.to_vec();
+    ranked.sort_by(cmp);
+    generate_i64(distribution, ranked.len(), options.disorder, options.seed)
+        .into_iter()
+        .map(|rank| ranked[rank as usize].clone())
+        .collect()
+}
+
+fn measure_dataset<D>(title: String, options: &ReportOptions, data: D) -> Dataset
+where
+    D: Fn(&str) -> Vec<Record>,
+{
+    let mut size = 0;
+    let rows = FIELDS
+        .into_iter()
+        .map(|field| {
+            let records = data(field);
+            size = records.len();
+            let cmp = column_comparator(field).unwrap();
+            let mut row: Vec<Measurement> = options
+                .algorithms
+                .iter()
+                .map(|algorithm| measure(&records, Some(algorithm), cmp, options))
+                .collect();
+            row.push(measure(&records, None, cmp, options));
+            for (name, measurement) in options.algorithms.iter().chain([&BASELINE]).zip(&row) {
+                println!(
+                    "{} / {} / {}: {}",
+                    title,
+                    field,
+                    name,
+                    format_elapsed(measurement)
+                );
+            }
+            (field, row)
+        })
+        .collect();
+
+    Dataset { title, size, rows }
+}
+
+// Sorts a copy of `records` with the algorithm, or slice::sort_by for None,
+// counting comparisons through a wrapping comparator.
+fn measure(
+    records: &[Record],
+    algorithm: Option<&str>,
+    cmp: fn(&Record, &Record) -> Ordering,
+    options: &ReportOptions,
+) -> Measurement {
+    let mut data = records.to_vec();
+    let comparisons = Cell::new(0u64);
+    let counted = |a: &Record, b: &Record| {
+        comparisons.set(comparisons.get() + 1);
+        cmp(a, b)
+    };
+    let config = SortConfig {
+        deadline: Some(Deadline::after(options.time_limit)),
+        ..options.config.clone()
+    };
+
+    let start = Instant::now();
+    let result = match algorithm {
+        Some(name) => find_sorter(name)
+            .expect("report algorithms are validated by the caller")
+            .sort_with_config(&mut data, &counted, &config),
+        None => {
+            data.sort_by(counted);
+            Ok(())
+        }
+    };
+    let elapsed = result.map(|_| start.elapsed());
+
+    Measurement {
+        sorted: elapsed.is_ok()
+            && data
+                .windows(2)
+                .all(|w| cmp(&w[0], &w[1]) != Ordering::Greater),
+        elapsed,
+        comparisons: comparisons.get(),
+    }
+}
+
+fn format_elapsed(measurement: &Measurement) -> String {
+    match &measurement.elapsed {
+        Ok(elapsed) if measurement.sorted => format!("{:.3} ms", elapsed.as_secs_f64() * 1000.0),
+        Ok(elapsed) => format!("{:.3} ms (not sorted!)", elapsed.as_secs_f64() * 1000.0),
+        Err(cancelled) => cancelled.to_string(),
+    }
+}
+
+fn render_markdown(datasets: &[Dataset], options: &ReportOptions) -> String {
+    // Writing into a String cannot fail.
+    let mut out = String::new();
+    writeln!(out, "# Sorting benchmark report\n").unwrap();
+    writeln!(
+        out,
+        "Quick Sort: insertion cutoff {}, {:?} pivot, seed {}. Time limit per sort: {:?}.",
+        options.config.insertion_cutoff,
+        options.config.pivot,
+        options.config.seed,
+        options.time_limit
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "Ratios are the algorithm's time divided by the time of `{}` on the same input.",
+        BASELINE
+    )
+    .unwrap();
+
+    let columns: Vec<&str> = options
+        .algorithms
+        .iter()
+        .copied()
+        .chain([BASELINE])
+        .collect();
+    for dataset in datasets {
+        writeln!(out, "\n## {}, {} records", dataset.title, dataset.size).unwrap();
+
+        write_table(&mut out, "Time", &columns, &dataset.rows, format_elapsed);
+        write_table(&mut out, "Comparisons", &columns, &dataset.rows, |m| {
+            m.comparisons.to_string()
+        });
+
+        writeln!(out, "\n### Ratio to {}\n", BASELINE).unwrap();
+        write_header(&mut out, &columns[..columns.len() - 1]);
+        for (field, row) in &dataset.rows {
+            let (baseline, measured) = row.split_last().unwrap();
+            write!(out, "| {} |", field).unwrap();
+            for measurement in measured {
+                let ratio = match (&measurement.elapsed, &baseline.elapsed) {
+                    (Ok(elapsed), Ok(base)) if !base.is_zero() => {
+                        format!("{:.2}x", elapsed.as_secs_f64() / base.as_secs_f64())
+                    }
+                    _ => "-".to_string(),
+                };
+                write!(out, " {} |", ratio).unwrap();
+            }
+            writeln!(out).unwrap();
+        }
+    }
+    out
+}
+
+fn write_table(
+    out: &mut String,
+    title: &str,
+    columns: &[&str],
+    rows: &[(&str, Vec<Measurement>)],
+    cell: impl Fn(&Measurement) -> String,
+) {
+    writeln!(out, "\n### {}\n", title).unwrap();
+    write_header(out, columns);
+    for (field, row) in rows {
+        write!(out, "| {} |", field).unwrap();
+        for measurement in row {
+            write!(out, " {} |", cell(measurement)).unwrap();
+        }
+        writeln!(out).unwrap();
+    }
+}
+
+fn write_header(out: &mut String, columns: &[&str]) {
+    write!(out, "| Field |").unwrap();
+    for column in columns {
+        write!(out, " {} |", column).unwrap();
+    }
+    write!(out, "\n|---|").unwrap();
+    for _ in columns {
+        write!(out, "---:|").unwrap();
+    }
+    writeln!(out).unwrap();
+}
@@ -0,0 +1,53 @@
+use std::cmp::Ordering;
+
+use crate::deadline::{Cancelled, WorkCounter};
+use crate::quicksort::SortConfig;
+
+// Ciura's empirically tuned gaps, extended geometrically for large inputs.
+const CIURA_GAPS: [usize; 9] = [1, 4, 10, 23, 57, 132, 301, 701, 1750];
+
+pub fn shell_sort<T, F>(arr: &mut [T], compar: &F)
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    // Without a deadline the sort always completes.
+    let _ = shell_sort_with_config(arr, compar, &SortConfig::default());
+}
+
+// Only the deadline and progress of the config apply to shell sort.
+pub fn shell_sort_with_config<T, F>(
+    arr: &mut [T],
+    compar: &F,
+    config: &SortConfig,
+) -> Result<(), Cancelled>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    let len = arr.len();
+    let mut gaps: Vec<usize> = CIURA_GAPS.into_iter().take_while(|&g| g < len).collect();
+    let mut next = CIURA_GAPS[CIURA_GAPS.len() - 1];
+    while next * 9 / 4 < len {
+        next = next * 9 / 4;
+        gaps.push(next);
+    }
+
+    if let Some(progress) = &config.progress {
+        progress.set_work(len * gaps.len());
+    }
+
+    let mut work = WorkCounter::default();
+    for &gap in gaps.iter().rev() {
+        for i in gap..len {
+            let mut j = i;
+            while j >= gap && compar(&arr[j - gap], &arr[j]) == Ordering::Greater {
+                arr.swap(j - gap, j);
+                j -= gap;
+            }
+            if work.add((i - j) / gap + 1) {
+                config.check_deadline()?;
+            }
+        }
+        config.report_progress(len);
+    }
+    Ok(())
+}
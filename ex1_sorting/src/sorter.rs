@@ -0,0 +1,146 @@
+use std::cmp::Ordering;
+
+use crate::adaptivemergesort::{adaptive_merge_sort, adaptive_merge_sort_with_config};
+use crate::deadline::Cancelled;
+use crate::heapsort::{heap_sort, heap_sort_with_config};
+use crate::insertionsort::{binary_insertion_sort, binary_insertion_sort_with_config};
+use crate::mergesort::{
+    bottom_up_merge_sort, bottom_up_merge_sort_with_config, merge_sort, merge_sort_with_config,
+};
+use crate::quicksort::{quick_sort, quick_sort_with_config, SortConfig};
+use crate::shellsort::{shell_sort, shell_sort_with_config};
+
+pub trait Sorter<T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    fn name(&self) -> &'static str;
+    fn is_stable(&self) -> bool;
+    fn sort(&self, base: &mut [T], compar: &F);
+
+    // Every algorithm checks the deadline while sorting; the tuning knobs
+    // only apply to quick sort.
+    fn sort_with_config(
+        &self,
+        base: &mut [T],
+        compar: &F,
+        config: &SortConfig,
+    ) -> Result<(), Cancelled>;
+}
+
+macro_rules! sorter {
+    ($type:ident, $name:expr, $stable:expr, $sort:path, $sort_with_config:path) => {
+        pub struct $type;
+
+        impl<T: Clone, F: Fn(&T, &T) -> Ordering> Sorter<T, F> for $type {
+            fn name(&self) -> &'static str {
+                $name
+            }
+
+            fn is_stable(&self) -> bool {
+                $stable
+            }
+
+            fn sort(&self, base: &mut [T], compar: &F) {
+                $sort(base, compar)
+            }
+
+            fn sort_with_config(
+                &self,
+                base: &mut [T],
+                compar: &F,
+                config: &SortConfig,
+            ) -> Result<(), Cancelled> {
+                $sort_with_config(base, compar, config)
+            }
+        }
+    };
+}
+
+sorter!(
+    MergeSort,
+    "Merge Sort",
+    true,
+    merge_sort,
+    merge_sort_with_config
+);
+sorter!(
+    QuickSort,
+    "Quick Sort",
+    false,
+    quick_sort,
+    quick_sort_with_config
+);
+sorter!(
+    AdaptiveMergeSort,
+    "Adaptive Merge Sort",
+    true,
+    adaptive_merge_sort,
+    adaptive_merge_sort_with_config
+);
+sorter!(
+    BottomUpMergeSort,
+    "Bottom-Up Merge Sort",
+    true,
+    bottom_up_merge_sort,
+    bottom_up_merge_sort_with_config
+);
+sorter!(
+    HeapSort,
+    "Heap Sort",
+    false,
+    heap_sort,
+    heap_sort_with_config
+);
+sorter!(
+    ShellSort,
+    "Shell Sort",
+    false,
+    shell_sort,
+    shell_sort_with_config
+);
+sorter!(
+    BinaryInsertionSort,
+    "Binary Insertion Sort",
+    true,
+    binary_insertion_sort,
+    binary_insertion_sort_with_config
+);
+
+// Adding an algorithm means declaring it above and listing it here.
+pub fn sorters<'a, T: Clone + 'a, F: Fn(&T, &T) -> Ordering + 'a>(
+) -> [&'a (dyn Sorter<T, F> + 'a); 7] {
+    [
+        &MergeSort,
+        &QuickSort,
+        &AdaptiveMergeSort,
+        &BottomUpMergeSort,
+        &HeapSort,
+        &ShellSort,
+        &BinaryInsertionSort,
+    ]
+}
+
+pub fn sorter_names() -> Vec<&'static str> {
+    sorters::<(), fn(&(), &()) -> Ordering>()
+        .iter()
+        .map(|sorter| sorter.name())
+        .collect()
+}
+
+// Accepts the full name ("Adaptive Merge Sort") or a short form without the
+// trailing "Sort", with dashes for spaces and optionally cut at a dash
+// ("adaptive-merge", "adaptive").
+pub fn find_sorter<'a, T: Clone + 'a, F: Fn(&T, &T) -> Ordering + 'a>(
+    name: &str,
+) -> Option<&'a (dyn Sorter<T, F> + 'a)> {
+    sorters().into_iter().find(|sorter| {
+        let full = sorter.name();
+        let slug = full.trim_end_matches(" Sort").replace(' ', "-");
+        full.eq_ignore_ascii_case(name)
+            || slug.eq_ignore_ascii_case(name)
+            || slug
+                .to_ascii_lowercase()
+                .starts_with(&format!("{}-", name.to_ascii_lowercase()))
+    })
+}
@@ -1,115 +1,98 @@
+use std::fmt::Debug;
+use std::fs;
+use std::str::FromStr;
+use std::time::Instant;
+
 use crate::compar::compare;
-use crate::mergesort;
-use crate::quicksort;
+use crate::error::SortError;
+use crate::sorter::find_sorter;
 
-pub fn run_sorting_test_i32(algorithm_choice: &str, test_case_choice: &str) {
-    match algorithm_choice {
-        "Merge Sort" => {
-            println!("You selected Merge Sort for i32");
-            let data = get_test_case_data_i32(test_case_choice);
-            let mut data_clone = data.clone();
-            println!("Unsorted data: {:?}", data_clone);
-            mergesort::merge_sort(&mut data_clone, &compare);
-            println!("Sorted data: {:?}", data_clone);
-        }
-        "Quick Sort" => {
-            println!("You selected Quick Sort for i32");
-            let data = get_test_case_data_i32(test_case_choice);
-            let mut data_clone = data.clone();
-            println!("Unsorted data: {:?}", data_clone);
-            quicksort::quick_sort(&mut data_clone, &compare);
-            println!("Sorted data: {:?}", data_clone);
-        }
-        _ => {
-            println!("Invalid algorithm selected.");
-        }
-    }
-}
+pub const DATA_TYPES: [&str; 5] = ["i32", "i64", "f32", "f64", "String"];
 
-// Function to run the sorting test for f32
-pub fn run_sorting_test_f32(algorithm_choice: &str, test_case_choice: &str) {
-    match algorithm_choice {
-        "Merge Sort" => {
-            println!("You selected Merge Sort for f32");
-            let data = get_test_case_data_f32(test_case_choice);
-            let mut data_clone = data.clone();
-            println!("Unsorted data: {:?}", data_clone);
-            mergesort::merge_sort(&mut data_clone, &compare);
-            println!("Sorted data: {:?}", data_clone);
-        }
-        "Quick Sort" => {
-            println!("You selected Quick Sort for f32");
-            let data = get_test_case_data_f32(test_case_choice);
-            let mut data_clone = data.clone();
-            println!("Unsorted data: {:?}", data_clone);
-            quicksort::quick_sort(&mut data_clone, &compare);
-            println!("Sorted data: {:?}", data_clone);
-        }
-        _ => {
-            println!("Invalid algorithm selected.");
-        }
+// Small inputs are printed in full before and after sorting.
+const PRINT_LIMIT: usize = 20;
+
+pub fn run_sorting_test(
+    algorithm_choice: &str,
+    data_type: &str,
+    path: &str,
+) -> Result<(), SortError> {
+    match data_type {
+        "i32" => run_typed_test::<i32>(algorithm_choice, data_type, path),
+        "i64" => run_typed_test::<i64>(algorithm_choice, data_type, path),
+        "f32" => run_typed_test::<f32>(algorithm_choice, data_type, path),
+        "f64" => run_typed_test::<f64>(algorithm_choice, data_type, path),
+        "String" => run_typed_test::<String>(algorithm_choice, data_type, path),
+        other => Err(SortError::Usage(format!("invalid data type '{}'", other))),
     }
 }
 
-// Function to run the sorting test for String
-pub fn run_sorting_test_string(algorithm_choice: &str, test_case_choice: &str) {
-    match algorithm_choice {
-        "Merge Sort" => {
-            println!("You selected Merge Sort for String");
-            let data = get_test_case_data_string(test_case_choice);
-            let mut data_clone = data.clone();
-            println!("Unsorted data: {:?}", data_clone);
-            mergesort::merge_sort(&mut data_clone, &compare);
-            println!("Sorted data: {:?}", data_clone);
-        }
-        "Quick Sort" => {
-            println!("You selected Quick Sort for String");
-            let data = get_test_case_data_string(test_case_choice);
-            let mut data_clone = data.clone();
-            println!("Unsorted data: {:?}", data_clone);
-            quicksort::quick_sort(&mut data_clone, &compare);
-            println!("Sorted data: {:?}", data_clone);
-        }
-        _ => {
-            println!("Invalid algorithm selected.");
-        }
+fn run_typed_test<T>(algorithm_choice: &str, type_name: &str, path: &str) -> Result<(), SortError>
+where
+    T: FromStr + Clone + PartialOrd + Debug,
+{
+    let sorter = find_sorter(algorithm_choice)
+        .ok_or_else(|| SortError::InvalidAlgorithm(algorithm_choice.to_string()))?;
+    println!("You selected {} for {}", sorter.name(), type_name);
+
+    let start_loading = Instant::now();
+    let mut data: Vec<T> = load_values(path, type_name)?;
+    println!(
+        "Loaded {} values in {:.4?}",
+        data.len(),
+        start_loading.elapsed()
+    );
+    if data.len() <= PRINT_LIMIT {
+        println!("Unsorted data: {:?}", data);
     }
-}
 
-// Function to return test case data for i32
-fn get_test_case_data_i32(test_case_choice: &str) -> Vec<i32> {
-    match test_case_choice {
-        "Test 1" => vec![3, 1, 4, 1, 5, 9],
-        "Test 2" => vec![10, 20, 15, 5, 25],
-        "Test 3" => vec![3, 1, 4, 1, 5, 9],
-        _ => vec![],
+    let start_sorting = Instant::now();
+    sorter.sort(&mut data, &compare);
+    println!("Sorting time: {:.4?}", start_sorting.elapsed());
+    if data.len() <= PRINT_LIMIT {
+        println!("Sorted data: {:?}", data);
     }
-}
 
-// Function to return test case data for f32
-fn get_test_case_data_f32(test_case_choice: &str) -> Vec<f32> {
-    match test_case_choice {
-        "Test 1" => vec![3.1, 1.1, 4.4, 1.5, 5.9],
-        "Test 2" => vec![10.2, 20.5, 15.7, 5.4, 25.8],
-        "Test 3" => vec![3.2, 1.3, 4.6, 1.2, 5.1],
-        _ => vec![],
+    match data
+        .windows(2)
+        .position(|pair| compare(&pair[0], &pair[1]) == std::cmp::Ordering::Greater)
+    {
+        None => {
+            println!("Verification: OK");
+            Ok(())
+        }
+        Some(index) => {
+            println!(
+                "Verification: FAILED at position {}: {:?} > {:?}",
+                index + 1,
+                data[index],
+                data[index + 1]
+            );
+            Err(SortError::VerificationFailed(path.to_string()))
+        }
     }
 }
 
-// Function to return test case data for String
-fn get_test_case_data_string(test_case_choice: &str) -> Vec<String> {
-    match test_case_choice {
-        "Test 1" => vec![
-            "apple".to_string(),
-            "banana".to_string(),
-            "cherry".to_string(),
-        ],
-        "Test 2" => vec!["dog".to_string(), "cat".to_string(), "elephant".to_string()],
-        "Test 3" => vec![
-            "orange".to_string(),
-            "pear".to_string(),
-            "grape".to_string(),
-        ],
-        _ => vec![],
+// Numbers are separated by any whitespace, so one per line and several per
+// line both work. Strings may contain spaces, so each non-empty line is one
+// value.
+fn load_values<T: FromStr>(path: &str, type_name: &str) -> Result<Vec<T>, SortError> {
+    let contents = fs::read_to_string(path).map_err(SortError::io(path))?;
+    let mut values = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let tokens: Box<dyn Iterator<Item = &str>> = if type_name == "String" {
+            Box::new(std::iter::once(line).filter(|line| !line.is_empty()))
+        } else {
+            Box::new(line.split_whitespace())
+        };
+        for token in tokens {
+            let value = token.parse().map_err(|_| SortError::Parse {
+                path: path.to_string(),
+                line: index + 1,
+                message: format!("invalid {} '{}'", type_name, token),
+            })?;
+            values.push(value);
+        }
     }
+    Ok(values)
 }
@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+use crate::error::SpellError;
+use crate::spell::{distance_function, run_check, CheckOptions, Format, FUNCTIONS};
+
+const USAGE: &str = "Usage:
+  edit_distance                         interactive menu
+  edit_distance <dictionary> <text> [--function <edit_distance|edit_distance_dyn>]
+                [--format <text|tsv|jsonl>]";
+
+struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+}
+
+impl Args {
+    fn parse(args: &[String], value_options: &[&str]) -> Result<Args, SpellError> {
+        let mut positional = Vec::new();
+        let mut options = HashMap::new();
+        let mut iter = args.iter();
+
+        while let Some(arg) = iter.next() {
+            if !arg.starts_with("--") {
+                positional.push(arg.clone());
+                continue;
+            }
+
+            let (name, inline_value) = match arg.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (arg.as_str(), None),
+            };
+            if !value_options.contains(&name) {
+                return Err(SpellError::Usage(format!(
+                    "unknown option '{}'\n{}",
+                    name, USAGE
+                )));
+            }
+            let value = match inline_value {
+                Some(value) => value,
+                None => iter.next().cloned().ok_or_else(|| {
+                    SpellError::Usage(format!("option '{}' requires a value", name))
+                })?,
+            };
+            options.insert(name.to_string(), value);
+        }
+
+        Ok(Args {
+            positional,
+            options,
+        })
+    }
+
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(String::as_str)
+    }
+}
+
+pub fn run(args: &[String]) -> Result<(), SpellError> {
+    if let Some("help" | "--help" | "-h") = args.first().map(String::as_str) {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+
+    let args = Args::parse(args, &["--function", "--format"])?;
+    let [dictionary, text] = args.positional.as_slice() else {
+        return Err(SpellError::Usage(USAGE.to_string()));
+    };
+
+    // The recursive edit_distance is exponential in the word length.
+    let function = args.option("--function").unwrap_or("edit_distance_dyn");
+    let distance = distance_function(function).ok_or_else(|| {
+        SpellError::Usage(format!(
+            "invalid function '{}', expected one of: {}",
+            function,
+            FUNCTIONS.join(", ")
+        ))
+    })?;
+
+    let format = match args.option("--format") {
+        Some(name) => Format::parse(name).ok_or_else(|| {
+            SpellError::Usage(format!(
+                "invalid format '{}', expected text, tsv or jsonl",
+                name
+            ))
+        })?,
+        None => Format::Text,
+    };
+
+    run_check(&CheckOptions {
+        dictionary,
+        text,
+        distance,
+        format,
+    })
+}
@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+use std::str;
+
+use memmap2::MmapOptions;
+use rayon::prelude::*;
+
+use crate::edit::edit_distance_dyn;
+
+pub type DistanceFn = fn(&str, &str) -> usize;
+
+// Candidates are only looked for among words whose length is within this
+// many characters of the misspelled word.
+const MAX_LENGTH_DIFFERENCE: usize = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    pub word: String,
+    pub distance: usize,
+}
+
+pub struct Dictionary {
+    // Sorted by length, keeping the original order among words of one length.
+    words: Vec<String>,
+    // The run of `words` with each length.
+    len_index: HashMap<usize, Range<usize>>,
+    distance: DistanceFn,
+}
+
+impl Dictionary {
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Dictionary> {
+        Ok(Dictionary::from_words(load_words(path)?))
+    }
+
+    pub fn from_words<I, S>(words: I) -> Dictionary
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut words: Vec<String> = words.into_iter().map(Into::into).collect();
+        words.sort_by_key(|word| word.len());
+
+        let mut len_index = HashMap::new();
+        let mut start = 0;
+        for run in words.chunk_by(|a, b| a.len() == b.len()) {
+            len_index.insert(run[0].len(), start..start + run.len());
+            start += run.len();
+        }
+
+        Dictionary {
+            words,
+            len_index,
+            distance: edit_distance_dyn,
+        }
+    }
+
+    // edit_distance_dyn unless changed here.
+    pub fn with_distance(mut self, distance: DistanceFn) -> Dictionary {
+        self.distance = distance;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    pub fn words(&self) -> impl Iterator<Item = &str> {
+        self.words.iter().map(String::as_str)
+    }
+
+    // Up to `max_results` words closest to `word`, nearest first. Ties come
+    // out shorter words first, then in dictionary order. The length window
+    // around `word` widens until it holds any words, up to
+    // MAX_LENGTH_DIFFERENCE; only that window is searched.
+    pub fn suggest(&self, word: &str, max_results: usize) -> Vec<Suggestion> {
+        if max_results == 0 {
+            return Vec::new();
+        }
+        let mut scored = self.score(word);
+        // Only the kept candidates are sorted and turned into Strings.
+        if max_results < scored.len() {
+            scored.select_nth_unstable(max_results - 1);
+            scored.truncate(max_results);
+        }
+        scored.sort_unstable();
+        self.suggestions(scored)
+    }
+
+    // All the words tied for the smallest distance to `word`, in the order
+    // `suggest` gives them, and that distance; usize::MAX if the length
+    // window holds no words.
+    pub fn closest(&self, word: &str) -> (Vec<Suggestion>, usize) {
+        let mut scored = self.score(word);
+        let min_distance = scored.iter().map(|&(distance, _)| distance).min();
+        let Some(min_distance) = min_distance else {
+            return (Vec::new(), usize::MAX);
+        };
+        scored.retain(|&(distance, _)| distance == min_distance);
+        scored.sort_unstable();
+        (self.suggestions(scored), min_distance)
+    }
+
+    // (distance, position in `words`) for every word in the length window
+    // around `word`; the position gives the tie-break.
+    fn score(&self, word: &str) -> Vec<(usize, usize)> {
+        let word_len = word.len();
+        let mut candidates: Vec<usize> = Vec::new();
+        for search_range in 1..=MAX_LENGTH_DIFFERENCE {
+            for len in word_len.saturating_sub(search_range)..=word_len + search_range {
+                // Words too short to be a single deletion away are skipped.
+                if len < word_len.saturating_sub(1) {
+                    continue;
+                }
+                if let Some(range) = self.len_index.get(&len) {
+                    candidates.extend(range.clone());
+                }
+            }
+            if !candidates.is_empty() {
+                break;
+            }
+        }
+
+        candidates
+            .par_iter()
+            .map(|&index| ((self.distance)(word, &self.words[index]), index))
+            .collect()
+    }
+
+    fn suggestions(&self, scored: Vec<(usize, usize)>) -> Vec<Suggestion> {
+        scored
+            .into_iter()
+            .map(|(distance, index)| Suggestion {
+                word: self.words[index].clone(),
+                distance,
+            })
+            .collect()
+    }
+}
+
+impl<S: Into<String>> FromIterator<S> for Dictionary {
+    fn from_iter<I: IntoIterator<Item = S>>(words: I) -> Dictionary {
+        Dictionary::from_words(words)
+    }
+}
+
+// Whitespace-separated words of a UTF-8 file.
+pub fn load_words<P: AsRef<Path>>(path: P) -> io::Result<Vec<String>> {
+    let file = File::open(path)?;
+
+    let mmap = unsafe { MmapOptions::new().map(&file)? };
+
+    let content =
+        str::from_utf8(&mmap).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    Ok(content.split_whitespace().map(|s| s.to_string()).collect())
+}
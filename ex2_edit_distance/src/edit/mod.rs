@@ -29,100 +29,54 @@ pub fn edit_distance(s1: &str, s2: &str) -> usize {
     min(d_no_op, min(d_del, d_ins))
 }
 
+// Same distance as edit_distance, computed bottom-up over two rows of the
+// table, so memory is O(min(m, n)) and the stack depth is constant.
 pub fn edit_distance_dyn(s1: &str, s2: &str) -> usize {
-    let bytes1 = s1.as_bytes();
-    let bytes2 = s2.as_bytes();
+    let (s1, s2) = trim_common_affixes(s1, s2);
 
-    let len1 = bytes1.len();
-    let len2 = bytes2.len();
-
-    let mut prefix_len = 0;
-    while prefix_len < len1 && prefix_len < len2 && bytes1[prefix_len] == bytes2[prefix_len] {
-        prefix_len += 1;
-    }
-
-    let mut suffix_len = 0;
-    while suffix_len + prefix_len < len1
-        && suffix_len + prefix_len < len2
-        && bytes1[len1 - 1 - suffix_len] == bytes2[len2 - 1 - suffix_len]
-    {
-        suffix_len += 1;
-    }
-
-    let s1 = &bytes1[prefix_len..len1 - suffix_len];
-    let s2 = &bytes2[prefix_len..len2 - suffix_len];
-
-    let m = s1.len();
-    let n = s2.len();
-
-    let mut memo = vec![None; (m + 1) * (n + 1)];
-    dp(s1, s2, 0, 0, &mut memo, m.max(n), n)
-}
-
-#[inline(always)]
-fn idx(i: usize, j: usize, n: usize) -> usize {
-    i * (n + 1) + j
-}
-
-fn dp(
-    s1: &[u8],
-    s2: &[u8],
-    i: usize,
-    j: usize,
-    memo: &mut [Option<usize>],
-    min_distance_found: usize,
-    n: usize,
-) -> usize {
-    let k = idx(i, j, n);
-    if let Some(cached) = memo[k] {
-        return cached;
-    }
-
-    let len1 = s1.len();
-    let len2 = s2.len();
-
-    // Manual abs_diff
-    let remaining = if len1 - i > len2 - j {
-        len1 - i - (len2 - j)
+    // The distance is symmetric, so the shorter string can index the rows.
+    let (long, short) = if s1.len() >= s2.len() {
+        (s1, s2)
     } else {
-        len2 - j - (len1 - i)
+        (s2, s1)
     };
-
-    if remaining >= min_distance_found {
-        return min_distance_found;
-    }
-
-    let result = if i == len1 {
-        len2 - j
-    } else if j == len2 {
-        len1 - i
-    } else if s1[i] == s2[j] {
-        return dp(s1, s2, i + 1, j + 1, memo, min_distance_found, n);
-    } else {
-        let d_del = 1 + dp(
-            s1,
-            s2,
-            i + 1,
-            j,
-            memo,
-            min_distance_found.saturating_sub(1),
-            n,
-        );
-        if d_del >= min_distance_found {
-            memo[k] = Some(min_distance_found);
-            return min_distance_found;
-        }
-
-        let d_ins = 1 + dp(s1, s2, i, j + 1, memo, d_del.saturating_sub(1), n);
-        if d_ins >= d_del {
-            memo[k] = Some(d_del);
-            return d_del;
+    let short: Vec<char> = short.chars().collect();
+
+    // prev[j]: distance between the long prefix so far and short[..j].
+    let mut prev: Vec<usize> = (0..=short.len()).collect();
+    let mut curr = vec![0; short.len() + 1];
+
+    for (i, c) in long.chars().enumerate() {
+        curr[0] = i + 1;
+        for j in 0..short.len() {
+            curr[j + 1] = if c == short[j] {
+                prev[j]
+            } else {
+                1 + prev[j + 1].min(curr[j])
+            };
         }
+        std::mem::swap(&mut prev, &mut curr);
+    }
 
-        let d_sub = 1 + dp(s1, s2, i + 1, j + 1, memo, d_ins.saturating_sub(1), n);
-        d_del.min(d_ins).min(d_sub)
-    };
+    prev[short.len()]
+}
 
-    memo[k] = Some(result);
-    result
+// A common prefix or suffix never needs an edit.
+fn trim_common_affixes<'a>(s1: &'a str, s2: &'a str) -> (&'a str, &'a str) {
+    let prefix: usize = s1
+        .chars()
+        .zip(s2.chars())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum();
+    let (s1, s2) = (&s1[prefix..], &s2[prefix..]);
+
+    let suffix: usize = s1
+        .chars()
+        .rev()
+        .zip(s2.chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum();
+    (&s1[..s1.len() - suffix], &s2[..s2.len() - suffix])
 }
@@ -0,0 +1,37 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum SpellError {
+    Io { path: String, source: io::Error },
+    Usage(String),
+}
+
+impl SpellError {
+    // For use with `map_err`, attaching the path the failing operation was on.
+    pub fn io(path: &str) -> impl FnOnce(io::Error) -> SpellError + '_ {
+        move |source| SpellError::Io {
+            path: path.to_string(),
+            source,
+        }
+    }
+}
+
+impl fmt::Display for SpellError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpellError::Io { path, source } => write!(f, "{}: {}", path, source),
+            SpellError::Usage(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for SpellError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SpellError::Io { source, .. } => Some(source),
+            SpellError::Usage(_) => None,
+        }
+    }
+}
@@ -0,0 +1,5 @@
+mod dictionary;
+mod edit;
+
+pub use dictionary::{load_words, Dictionary, DistanceFn, Suggestion};
+pub use edit::{edit_distance, edit_distance_dyn};
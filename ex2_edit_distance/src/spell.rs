@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use edit_distance::{edit_distance, edit_distance_dyn, load_words, Dictionary, DistanceFn};
+use regex::Regex;
+
+use crate::error::SpellError;
+
+pub const FUNCTIONS: [&str; 2] = ["edit_distance", "edit_distance_dyn"];
+
+pub fn distance_function(name: &str) -> Option<DistanceFn> {
+    match name {
+        "edit_distance" => Some(edit_distance),
+        "edit_distance_dyn" => Some(edit_distance_dyn),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    // Progress, one line per word and a performance summary, for people.
+    Text,
+    // One line per word: word, distance, comma-separated suggestions.
+    Tsv,
+    // One JSON object per word.
+    Jsonl,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Option<Format> {
+        match name.to_ascii_lowercase().as_str() {
+            "text" => Some(Format::Text),
+            "tsv" => Some(Format::Tsv),
+            "jsonl" => Some(Format::Jsonl),
+            _ => None,
+        }
+    }
+}
+
+pub struct CheckOptions<'a> {
+    pub dictionary: &'a str,
+    pub text: &'a str,
+    pub distance: DistanceFn,
+    pub format: Format,
+}
+
+pub fn run_check(options: &CheckOptions) -> Result<(), SpellError> {
+    let total_start = Instant::now();
+    let text_format = options.format == Format::Text;
+    let status = |message: &str| {
+        if text_format {
+            println!("{}", message);
+        }
+    };
+
+    status("Loading dictionary...");
+    let dict_load_start = Instant::now();
+    let words = load_words(options.dictionary).map_err(SpellError::io(options.dictionary))?;
+    let dict_load_time = dict_load_start.elapsed();
+
+    status("Loading text to correct...");
+    let correctme = load_words(options.text).map_err(SpellError::io(options.text))?;
+
+    status("Building length index...");
+    let index_start = Instant::now();
+    let dictionary = Dictionary::from_words(words).with_distance(options.distance);
+    let index_time = index_start.elapsed();
+
+    let re = Regex::new(r"\b[a-zA-Z]+\b").unwrap();
+    let mut cache: HashMap<String, (Vec<String>, usize)> = HashMap::new();
+
+    status("Building cache...");
+    let cache_start = Instant::now();
+    for dict_word in dictionary.words() {
+        let word_lower = dict_word.to_lowercase();
+        cache.insert(word_lower, (vec![dict_word.to_string()], 0));
+    }
+    let cache_time = cache_start.elapsed();
+
+    status("\nProcessing text...");
+    if options.format == Format::Tsv {
+        println!("word\tdistance\tsuggestions");
+    }
+    let process_start = Instant::now();
+    let mut total_words = 0;
+    let mut cached_words = 0;
+    let mut processed_words = 0;
+
+    for sentence in &correctme {
+        status(&format!("\nProcessing sentence: {}", sentence));
+
+        for word in re.find_iter(sentence) {
+            total_words += 1;
+            let word_lower = word.as_str().to_lowercase();
+
+            if let Some((closest_words, distance)) = cache.get(&word_lower) {
+                cached_words += 1;
+                print_correction(options.format, &word_lower, closest_words, *distance, None);
+                continue;
+            }
+
+            processed_words += 1;
+            let word_start = Instant::now();
+            let (closest_words, min_distance) = closest_words(&dictionary, &word_lower);
+
+            print_correction(
+                options.format,
+                &word_lower,
+                &closest_words,
+                min_distance,
+                Some(word_start.elapsed()),
+            );
+            cache.insert(word_lower, (closest_words, min_distance));
+        }
+    }
+
+    if text_format {
+        let stats = Stats {
+            dict_size: dictionary.len(),
+            total_words,
+            cached_words,
+            processed_words,
+            dict_load_time,
+            index_time,
+            cache_time,
+            process_time: process_start.elapsed(),
+            total_time: total_start.elapsed(),
+        };
+        stats.print();
+    }
+    Ok(())
+}
+
+// `elapsed` is None for words answered from the cache. A word with no
+// suggestions has distance usize::MAX.
+fn print_correction(
+    format: Format,
+    word: &str,
+    closest_words: &[String],
+    distance: usize,
+    elapsed: Option<Duration>,
+) {
+    let distance_text = if closest_words.is_empty() {
+        None
+    } else {
+        Some(distance.to_string())
+    };
+
+    match format {
+        Format::Text => match elapsed {
+            None if distance == 0 => {
+                println!("  '{}' → Exact dictionary match: {:?}", word, closest_words)
+            }
+            None => println!(
+                "  '{}' → Closest match(es): {:?} (distance: {}, cached)",
+                word, closest_words, distance
+            ),
+            Some(elapsed) => println!(
+                "  '{}' → Closest match(es): {:?} (distance: {}) in {:.2?}",
+                word, closest_words, distance, elapsed
+            ),
+        },
+        Format::Tsv => println!(
+            "{}\t{}\t{}",
+            word,
+            distance_text.unwrap_or_default(),
+            closest_words.join(",")
+        ),
+        Format::Jsonl => {
+            let suggestions: Vec<String> = closest_words.iter().map(|w| json_string(w)).collect();
+            println!(
+                "{{\"word\":{},\"distance\":{},\"suggestions\":[{}]}}",
+                json_string(word),
+                distance_text.as_deref().unwrap_or("null"),
+                suggestions.join(",")
+            );
+        }
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// All the suggestions tied for the smallest distance, usize::MAX if there
+// are none.
+fn closest_words(dictionary: &Dictionary, word: &str) -> (Vec<String>, usize) {
+    let (suggestions, min_distance) = dictionary.closest(word);
+    let closest_words = suggestions
+        .into_iter()
+        .map(|suggestion| suggestion.word)
+        .collect();
+    (closest_words, min_distance)
+}
+
+struct Stats {
+    dict_size: usize,
+    total_words: usize,
+    cached_words: usize,
+    processed_words: usize,
+    dict_load_time: Duration,
+    index_time: Duration,
+    cache_time: Duration,
+    process_time: Duration,
+    total_time: Duration,
+}
+
+impl Stats {
+    fn print(&self) {
+        println!("\n=== Performance Summary ===");
+        println!("Dictionary:");
+        println!("  - Words: {}", self.dict_size);
+        println!("  - Load time: {:.2?}", self.dict_load_time);
+        println!("  - Index build time: {:.2?}", self.index_time);
+        println!("  - Cache build time: {:.2?}", self.cache_time);
+        println!("\nText Processing:");
+        println!("  - Total words: {}", self.total_words);
+        println!(
+            "  - Cached words: {} ({:.1}%)",
+            self.cached_words,
+            (self.cached_words as f32 / self.total_words as f32) * 100.0
+        );
+        println!("  - Processed words: {}", self.processed_words);
+        println!("  - Processing time: {:.2?}", self.process_time);
+        println!(
+            "  - Average time per word: {:.2?}",
+            self.process_time / std::cmp::max(self.processed_words, 1) as u32
+        );
+        println!("\nTotal execution time: {:.2?}", self.total_time);
+    }
+}
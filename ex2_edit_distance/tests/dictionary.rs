@@ -0,0 +1,69 @@
+use edit_distance::{Dictionary, Suggestion};
+
+fn suggestion(word: &str, distance: usize) -> Suggestion {
+    Suggestion {
+        word: word.to_string(),
+        distance,
+    }
+}
+
+#[test]
+fn suggest_orders_by_distance_then_length_then_dictionary_order() {
+    let dictionary = Dictionary::from_words(["cast", "casa", "cassa", "caso", "cara", "cas"]);
+    assert_eq!(
+        dictionary.suggest("casa", 10),
+        vec![
+            suggestion("casa", 0),
+            suggestion("cas", 1),
+            suggestion("cassa", 1),
+            suggestion("cast", 2),
+            suggestion("caso", 2),
+            suggestion("cara", 2),
+        ]
+    );
+}
+
+#[test]
+fn suggest_keeps_the_nearest_max_results() {
+    let dictionary = Dictionary::from_words(["cast", "casa", "cassa", "caso", "cara"]);
+    assert_eq!(
+        dictionary.suggest("casa", 3),
+        vec![
+            suggestion("casa", 0),
+            suggestion("cassa", 1),
+            suggestion("cast", 2),
+        ]
+    );
+    assert_eq!(dictionary.suggest("casa", 0), vec![]);
+}
+
+#[test]
+fn suggest_on_an_empty_dictionary_finds_nothing() {
+    let dictionary: Dictionary = Vec::<String>::new().into_iter().collect();
+    assert!(dictionary.is_empty());
+    assert_eq!(dictionary.suggest("casa", 5), vec![]);
+}
+
+#[test]
+fn suggest_widens_the_length_window_until_it_finds_words() {
+    // Nothing within one character of the length; two away there is.
+    let dictionary = Dictionary::from_words(["ab", "abcdef", "abcdefgh"]);
+    assert_eq!(dictionary.suggest("abcd", 5), vec![suggestion("abcdef", 2)]);
+
+    // Past MAX_LENGTH_DIFFERENCE the window stops widening.
+    let dictionary = Dictionary::from_words(["abcdefgh"]);
+    assert_eq!(dictionary.suggest("abcd", 5), vec![]);
+}
+
+#[test]
+fn closest_keeps_only_the_words_tied_for_the_smallest_distance() {
+    let dictionary = Dictionary::from_words(["cast", "casa", "cassa", "caso", "cara", "cas"]);
+    assert_eq!(
+        dictionary.closest("cass"),
+        (vec![suggestion("cas", 1), suggestion("cassa", 1)], 1)
+    );
+    assert_eq!(dictionary.closest("cara"), (vec![suggestion("cara", 0)], 0));
+
+    let dictionary = Dictionary::from_words(["abcdefgh"]);
+    assert_eq!(dictionary.closest("abcd"), (vec![], usize::MAX));
+}
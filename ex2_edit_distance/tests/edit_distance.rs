@@ -0,0 +1,112 @@
+use edit_distance::{edit_distance, edit_distance_dyn};
+
+// xorshift64, enough to vary the inputs reproducibly.
+struct Rng(u64);
+
+impl Rng {
+    fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % n as u64) as usize
+    }
+
+    // A small alphabet with a multi-byte character makes matches and shared
+    // affixes common.
+    fn string(&mut self, max_len: usize) -> String {
+        const ALPHABET: [char; 4] = ['a', 'b', 'c', 'é'];
+        let len = self.below(max_len + 1);
+        (0..len)
+            .map(|_| ALPHABET[self.below(ALPHABET.len())])
+            .collect()
+    }
+}
+
+// The memoized recursion edit_distance_dyn used to be, kept as a reference
+// that is fast enough for long strings. It works on chars and only inserts
+// and deletes, like edit_distance.
+fn memoized(s1: &str, s2: &str) -> usize {
+    let s1: Vec<char> = s1.chars().collect();
+    let s2: Vec<char> = s2.chars().collect();
+    let mut memo = vec![None; (s1.len() + 1) * (s2.len() + 1)];
+    dp(&s1, &s2, 0, 0, &mut memo)
+}
+
+fn dp(s1: &[char], s2: &[char], i: usize, j: usize, memo: &mut [Option<usize>]) -> usize {
+    let k = i * (s2.len() + 1) + j;
+    if let Some(cached) = memo[k] {
+        return cached;
+    }
+
+    let result = if i == s1.len() {
+        s2.len() - j
+    } else if j == s2.len() {
+        s1.len() - i
+    } else if s1[i] == s2[j] {
+        dp(s1, s2, i + 1, j + 1, memo)
+    } else {
+        let d_del = 1 + dp(s1, s2, i + 1, j, memo);
+        let d_ins = 1 + dp(s1, s2, i, j + 1, memo);
+        d_del.min(d_ins)
+    };
+
+    memo[k] = Some(result);
+    result
+}
+
+#[test]
+fn dyn_matches_recursive_on_random_strings() {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    // Short, since edit_distance is exponential.
+    for _ in 0..2000 {
+        let s1 = rng.string(7);
+        let s2 = rng.string(7);
+        assert_eq!(
+            edit_distance_dyn(&s1, &s2),
+            edit_distance(&s1, &s2),
+            "edit distance of {:?} and {:?}",
+            s1,
+            s2
+        );
+    }
+}
+
+#[test]
+fn dyn_matches_memoized_on_long_random_strings() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    for _ in 0..300 {
+        let s1 = rng.string(200);
+        let s2 = rng.string(200);
+        assert_eq!(
+            edit_distance_dyn(&s1, &s2),
+            memoized(&s1, &s2),
+            "edit distance of {:?} and {:?}",
+            s1,
+            s2
+        );
+    }
+}
+
+#[test]
+fn dyn_matches_definition_examples() {
+    for (s1, s2, distance) in [
+        ("casa", "cassa", 1),
+        ("casa", "cara", 2),
+        ("vinaio", "vino", 2),
+        ("tassa", "passato", 4),
+        ("pioppo", "pioppo", 0),
+        ("", "abc", 3),
+        ("abc", "", 3),
+    ] {
+        assert_eq!(edit_distance_dyn(s1, s2), distance, "{} / {}", s1, s2);
+    }
+}
+
+#[test]
+fn dyn_handles_long_strings() {
+    let s1 = "ab".repeat(2_000);
+    let s2 = "ba".repeat(1_000);
+    // s2 is a subsequence of s1, so only deletions are needed.
+    assert_eq!(edit_distance_dyn(&s1, &s2), s1.len() - s2.len());
+    assert_eq!(edit_distance_dyn(&s2, &s1), s1.len() - s2.len());
+}